@@ -0,0 +1,475 @@
+//! Typed builders for every anchor-escrow instruction.
+
+use anchor_escrow::{accounts, instruction, instructions::MakeArgs, state::Order};
+use anchor_lang::{
+    prelude::Pubkey,
+    solana_program::{instruction::Instruction, system_program, sysvar},
+    InstructionData, ToAccountMetas,
+};
+use anchor_spl::associated_token::{self, get_associated_token_address_with_program_id};
+use solana_ed25519_program::new_ed25519_instruction_with_signature;
+
+use crate::{pda, Offer, PROGRAM_ID};
+
+/// slippage bounds a taker signs against
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TakeArgs {
+    /// fail if the maker amended the offer since this revision was read
+    pub expected_revision: Option<u64>,
+    /// most mint b the taker is willing to pay
+    pub expected_receive: u64,
+    /// least mint a the taker is willing to get
+    pub min_amount_a: u64,
+}
+
+impl TakeArgs {
+    pub fn new(expected_receive: u64, min_amount_a: u64) -> Self {
+        Self {
+            expected_revision: None,
+            expected_receive,
+            min_amount_a,
+        }
+    }
+}
+
+fn instruction(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+/// `admin` has to be the program's upgrade authority
+pub fn init_config_ix(admin: &Pubkey, fee_bps: u16, fee_recipient: &Pubkey) -> Instruction {
+    instruction(
+        accounts::InitConfig {
+            admin: *admin,
+            config: pda::config(),
+            program_data: pda::program_data(),
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::InitConfig {
+            fee_bps,
+            fee_recipient: *fee_recipient,
+        },
+    )
+}
+
+/// `None` leaves a setting unchanged
+pub fn update_config_ix(
+    admin: &Pubkey,
+    new_admin: Option<Pubkey>,
+    fee_bps: Option<u16>,
+    fee_recipient: Option<Pubkey>,
+    paused: Option<bool>,
+) -> Instruction {
+    instruction(
+        accounts::UpdateConfig {
+            admin: *admin,
+            config: pda::config(),
+            event_authority: pda::event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::UpdateConfig {
+            admin: new_admin,
+            fee_bps,
+            fee_recipient,
+            paused,
+        },
+    )
+}
+
+pub fn make_ix(offer: &Offer, deposit: u64, receive: u64, args: MakeArgs) -> Instruction {
+    instruction(
+        accounts::Make {
+            maker: offer.maker,
+            mint_a: offer.mint_a,
+            mint_b: offer.mint_b,
+            maker_ata_a: offer.ata(&offer.maker, &offer.mint_a),
+            escrow: offer.escrow(),
+            vault: offer.vault(),
+            config: pda::config(),
+            associated_token_program: associated_token::ID,
+            token_program: offer.token_program,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::Make {
+            seed: offer.seed,
+            deposit,
+            receive,
+            args,
+        },
+    )
+}
+
+/// `fee_recipient` is the one stored in the config, see [`crate::decode_config`]
+///
+/// SOL offers are paid out as lamports when the taker has no wrapped SOL account yet, otherwise
+/// the released SOL is added to the taker's wrapped SOL and left for them to unwrap
+pub fn take_ix(
+    offer: &Offer,
+    taker: &Pubkey,
+    fee_recipient: &Pubkey,
+    args: TakeArgs,
+    preimage: Option<Vec<u8>>,
+) -> Instruction {
+    instruction(
+        accounts::Take {
+            taker: *taker,
+            maker: offer.maker,
+            mint_a: offer.mint_a,
+            mint_b: offer.mint_b,
+            taker_ata_a: offer.ata(taker, &offer.mint_a),
+            taker_ata_b: offer.ata(taker, &offer.mint_b),
+            maker_ata_b: offer.ata(&offer.maker, &offer.mint_b),
+            escrow: offer.escrow(),
+            vault: offer.vault(),
+            config: pda::config(),
+            fee_recipient: *fee_recipient,
+            treasury_ata_b: offer.ata(fee_recipient, &offer.mint_b),
+            associated_token_program: associated_token::ID,
+            token_program: offer.token_program,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::Take {
+            expected_revision: args.expected_revision,
+            expected_receive: args.expected_receive,
+            min_amount_a: args.min_amount_a,
+            preimage,
+        },
+    )
+}
+
+/// takes `amount` of the mint b asked for and the matching share of the vault, paid out in SOL
+/// the same way as [`take_ix`]
+pub fn take_partial_ix(
+    offer: &Offer,
+    taker: &Pubkey,
+    fee_recipient: &Pubkey,
+    amount: u64,
+    args: TakeArgs,
+) -> Instruction {
+    instruction(
+        accounts::TakePartial {
+            taker: *taker,
+            maker: offer.maker,
+            mint_a: offer.mint_a,
+            mint_b: offer.mint_b,
+            taker_ata_a: offer.ata(taker, &offer.mint_a),
+            taker_ata_b: offer.ata(taker, &offer.mint_b),
+            maker_ata_b: offer.ata(&offer.maker, &offer.mint_b),
+            escrow: offer.escrow(),
+            vault: offer.vault(),
+            config: pda::config(),
+            fee_recipient: *fee_recipient,
+            treasury_ata_b: offer.ata(fee_recipient, &offer.mint_b),
+            associated_token_program: associated_token::ID,
+            token_program: offer.token_program,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::TakePartial {
+            amount,
+            expected_revision: args.expected_revision,
+            expected_receive: args.expected_receive,
+            min_amount_a: args.min_amount_a,
+        },
+    )
+}
+
+pub fn refund_ix(offer: &Offer) -> Instruction {
+    instruction(
+        accounts::Refund {
+            maker: offer.maker,
+            mint_a: offer.mint_a,
+            maker_ata_a: offer.ata(&offer.maker, &offer.mint_a),
+            escrow: offer.escrow(),
+            vault: offer.vault(),
+            associated_token_program: associated_token::ID,
+            token_program: offer.token_program,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::Refund {},
+    )
+}
+
+/// refunds an expired offer to its maker, anyone can send it and collect the crank tip
+pub fn crank_refund_ix(offer: &Offer, cranker: &Pubkey) -> Instruction {
+    instruction(
+        accounts::CrankRefund {
+            cranker: *cranker,
+            maker: offer.maker,
+            mint_a: offer.mint_a,
+            maker_ata_a: offer.ata(&offer.maker, &offer.mint_a),
+            escrow: offer.escrow(),
+            vault: offer.vault(),
+            associated_token_program: associated_token::ID,
+            token_program: offer.token_program,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::CrankRefund {},
+    )
+}
+
+pub fn amend_ix(offer: &Offer, top_up: u64, withdraw: u64, receive: u64) -> Instruction {
+    instruction(
+        accounts::Amend {
+            maker: offer.maker,
+            mint_a: offer.mint_a,
+            maker_ata_a: offer.ata(&offer.maker, &offer.mint_a),
+            escrow: offer.escrow(),
+            vault: offer.vault(),
+            token_program: offer.token_program,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::Amend {
+            top_up,
+            withdraw,
+            receive,
+        },
+    )
+}
+
+pub fn fund_arbitrated_ix(offer: &Offer, taker: &Pubkey, args: TakeArgs) -> Instruction {
+    instruction(
+        accounts::FundArbitrated {
+            taker: *taker,
+            maker: offer.maker,
+            mint_a: offer.mint_a,
+            mint_b: offer.mint_b,
+            taker_ata_b: offer.ata(taker, &offer.mint_b),
+            escrow: offer.escrow(),
+            vault: offer.vault(),
+            vault_b: offer.vault_b(),
+            config: pda::config(),
+            associated_token_program: associated_token::ID,
+            token_program: offer.token_program,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::FundArbitrated {
+            expected_revision: args.expected_revision,
+            expected_receive: args.expected_receive,
+            min_amount_a: args.min_amount_a,
+        },
+    )
+}
+
+/// `party` is the maker or the taker that funded the escrow
+pub fn raise_dispute_ix(offer: &Offer, party: &Pubkey) -> Instruction {
+    instruction(
+        accounts::RaiseDispute {
+            party: *party,
+            escrow: offer.escrow(),
+            event_authority: pda::event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::RaiseDispute {},
+    )
+}
+
+/// `authority` is the arbiter, or either party for a 10000 or 0 split before a dispute,
+/// `fee_recipient` is the one stored in the config
+pub fn release_ix(
+    offer: &Offer,
+    authority: &Pubkey,
+    taker: &Pubkey,
+    fee_recipient: &Pubkey,
+    taker_bps: u16,
+) -> Instruction {
+    instruction(
+        accounts::Release {
+            authority: *authority,
+            maker: offer.maker,
+            taker: *taker,
+            mint_a: offer.mint_a,
+            mint_b: offer.mint_b,
+            maker_ata_a: offer.ata(&offer.maker, &offer.mint_a),
+            maker_ata_b: offer.ata(&offer.maker, &offer.mint_b),
+            taker_ata_a: offer.ata(taker, &offer.mint_a),
+            taker_ata_b: offer.ata(taker, &offer.mint_b),
+            escrow: offer.escrow(),
+            vault: offer.vault(),
+            vault_b: offer.vault_b(),
+            config: pda::config(),
+            fee_recipient: *fee_recipient,
+            treasury_ata_b: offer.ata(fee_recipient, &offer.mint_b),
+            associated_token_program: associated_token::ID,
+            token_program: offer.token_program,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::Release { taker_bps },
+    )
+}
+
+/// `expected_revision` fails the contribution if the maker amended the offer since it was read
+pub fn contribute_ix(
+    offer: &Offer,
+    taker: &Pubkey,
+    fee_recipient: &Pubkey,
+    amount: u64,
+    expected_revision: Option<u64>,
+) -> Instruction {
+    let escrow = offer.escrow();
+
+    instruction(
+        accounts::Contribute {
+            taker: *taker,
+            maker: offer.maker,
+            mint_b: offer.mint_b,
+            taker_ata_b: offer.ata(taker, &offer.mint_b),
+            maker_ata_b: offer.ata(&offer.maker, &offer.mint_b),
+            escrow,
+            vault_b: offer.vault_b(),
+            receipt: pda::receipt(&escrow, taker),
+            config: pda::config(),
+            fee_recipient: *fee_recipient,
+            treasury_ata_b: offer.ata(fee_recipient, &offer.mint_b),
+            associated_token_program: associated_token::ID,
+            token_program: offer.token_program,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::Contribute {
+            amount,
+            expected_revision,
+        },
+    )
+}
+
+pub fn claim_ix(offer: &Offer, taker: &Pubkey) -> Instruction {
+    let escrow = offer.escrow();
+
+    instruction(
+        accounts::Claim {
+            taker: *taker,
+            maker: offer.maker,
+            mint_a: offer.mint_a,
+            taker_ata_a: offer.ata(taker, &offer.mint_a),
+            escrow,
+            vault: offer.vault(),
+            receipt: pda::receipt(&escrow, taker),
+            associated_token_program: associated_token::ID,
+            token_program: offer.token_program,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::Claim {},
+    )
+}
+
+pub fn refund_contribution_ix(offer: &Offer, taker: &Pubkey) -> Instruction {
+    let escrow = offer.escrow();
+
+    instruction(
+        accounts::RefundContribution {
+            taker: *taker,
+            maker: offer.maker,
+            mint_b: offer.mint_b,
+            taker_ata_b: offer.ata(taker, &offer.mint_b),
+            escrow,
+            vault_b: offer.vault_b(),
+            receipt: pda::receipt(&escrow, taker),
+            token_program: offer.token_program,
+            event_authority: pda::event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::RefundContribution {},
+    )
+}
+
+/// the Ed25519 signature check and the fill, to be sent in this order in one transaction
+///
+/// `signature` is the maker's signature over `order.message()`
+pub fn fill_order_ixs(
+    order: &Order,
+    signature: &[u8; 64],
+    taker: &Pubkey,
+    fee_recipient: &Pubkey,
+    token_program: &Pubkey,
+) -> [Instruction; 2] {
+    let message = order
+        .message()
+        .expect("borsh encoding into a Vec does not fail");
+    let verify_ix =
+        new_ed25519_instruction_with_signature(&message, signature, &order.maker.to_bytes());
+
+    let ata = |owner: &Pubkey, mint: &Pubkey| {
+        get_associated_token_address_with_program_id(owner, mint, token_program)
+    };
+    let fill_ix = instruction(
+        accounts::FillOrder {
+            taker: *taker,
+            maker: order.maker,
+            mint_a: order.mint_a,
+            mint_b: order.mint_b,
+            maker_ata_a: ata(&order.maker, &order.mint_a),
+            maker_ata_b: ata(&order.maker, &order.mint_b),
+            taker_ata_a: ata(taker, &order.mint_a),
+            taker_ata_b: ata(taker, &order.mint_b),
+            delegate: pda::delegate(),
+            order_nonce: pda::order_nonce(&order.maker, order.nonce),
+            config: pda::config(),
+            fee_recipient: *fee_recipient,
+            treasury_ata_b: ata(fee_recipient, &order.mint_b),
+            instructions: sysvar::instructions::ID,
+            associated_token_program: associated_token::ID,
+            token_program: *token_program,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::FillOrder {
+            order: order.clone(),
+        },
+    );
+
+    [verify_ix, fill_ix]
+}
+
+pub fn cancel_order_ix(maker: &Pubkey, nonce: u64) -> Instruction {
+    instruction(
+        accounts::CancelOrder {
+            maker: *maker,
+            order_nonce: pda::order_nonce(maker, nonce),
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::CancelOrder { nonce },
+    )
+}
+
+/// upgrades an escrow written by an older program version, `payer` covers the extra rent
+pub fn migrate_escrow_ix(offer: &Offer, payer: &Pubkey) -> Instruction {
+    instruction(
+        accounts::MigrateEscrow {
+            payer: *payer,
+            escrow: offer.escrow(),
+            vault: offer.vault(),
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::MigrateEscrow {},
+    )
+}
@@ -0,0 +1,66 @@
+//! Rust client for the anchor-escrow program: typed instruction builders, PDA helpers and
+//! account decoding, so services and tests don't hand-roll account lists.
+//!
+//! Builders return a plain [`Instruction`]. Token-2022 transfer-hook extra accounts and bundle
+//! legs go after the builder's accounts, push them onto `Instruction::accounts`.
+//!
+//! Enable the `no-entrypoint` feature when linking next to another on-chain program's crate.
+
+pub mod instructions;
+pub mod pda;
+pub mod state;
+
+pub use anchor_escrow::{instructions::MakeArgs, ID as PROGRAM_ID};
+pub use anchor_lang::{prelude::Pubkey, solana_program::instruction::Instruction};
+pub use instructions::*;
+pub use state::*;
+
+use anchor_spl::{associated_token::get_associated_token_address_with_program_id, token};
+
+/// one escrow offer: its maker and seed, both mints and the token program they live under
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Offer {
+    pub maker: Pubkey,
+    pub seed: u64,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub token_program: Pubkey,
+}
+
+impl Offer {
+    /// an offer between two SPL Token mints
+    pub fn new(maker: Pubkey, seed: u64, mint_a: Pubkey, mint_b: Pubkey) -> Self {
+        Self {
+            maker,
+            seed,
+            mint_a,
+            mint_b,
+            token_program: token::ID,
+        }
+    }
+
+    /// for mints owned by another token program, e.g. Token-2022
+    pub fn with_token_program(mut self, token_program: Pubkey) -> Self {
+        self.token_program = token_program;
+        self
+    }
+
+    pub fn escrow(&self) -> Pubkey {
+        pda::escrow(&self.maker, self.seed).0
+    }
+
+    /// escrow owned mint a account holding the maker's deposit
+    pub fn vault(&self) -> Pubkey {
+        self.ata(&self.escrow(), &self.mint_a)
+    }
+
+    /// escrow owned mint b account of arbitrated and crowd-filled offers
+    pub fn vault_b(&self) -> Pubkey {
+        self.ata(&self.escrow(), &self.mint_b)
+    }
+
+    /// associated token account of `owner` for `mint` under the offer's token program
+    pub fn ata(&self, owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(owner, mint, &self.token_program)
+    }
+}
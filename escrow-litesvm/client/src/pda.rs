@@ -0,0 +1,47 @@
+//! Program derived addresses of anchor-escrow.
+
+use anchor_lang::{prelude::Pubkey, solana_program::bpf_loader_upgradeable};
+
+use crate::PROGRAM_ID;
+
+/// protocol fee and pause settings, seeds = [b"config"]
+pub fn config() -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], &PROGRAM_ID).0
+}
+
+/// upgradeable loader account holding the program's upgrade authority, seeds = [program id]
+pub fn program_data() -> Pubkey {
+    Pubkey::find_program_address(&[PROGRAM_ID.as_ref()], &bpf_loader_upgradeable::ID).0
+}
+
+/// signer of the program's self-CPI event logs
+pub fn event_authority() -> Pubkey {
+    Pubkey::find_program_address(&[b"__event_authority"], &PROGRAM_ID).0
+}
+
+/// seeds = [b"escrow", maker, seed]
+pub fn escrow(maker: &Pubkey, seed: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &PROGRAM_ID,
+    )
+}
+
+/// a taker's contribution to a crowd-filled escrow, seeds = [b"receipt", escrow, taker]
+pub fn receipt(escrow: &Pubkey, taker: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"receipt", escrow.as_ref(), taker.as_ref()], &PROGRAM_ID).0
+}
+
+/// spent marker of a signed order nonce, seeds = [b"order", maker, nonce]
+pub fn order_nonce(maker: &Pubkey, nonce: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"order", maker.as_ref(), &nonce.to_le_bytes()],
+        &PROGRAM_ID,
+    )
+    .0
+}
+
+/// delegate makers approve on their mint a account to post signed orders
+pub fn delegate() -> Pubkey {
+    Pubkey::find_program_address(&[b"delegate"], &PROGRAM_ID).0
+}
@@ -0,0 +1,21 @@
+//! Decoding of anchor-escrow accounts.
+
+use anchor_lang::{AccountDeserialize, Result};
+
+pub use anchor_escrow::state::{
+    BundleLeg, Config, Escrow, Order, OrderNonce, Receipt, ESCROW_VERSION,
+};
+
+/// decodes an escrow account, layouts other than `ESCROW_VERSION` fail with
+/// `UnsupportedEscrowVersion` and have to be migrated first
+pub fn decode_escrow(data: &[u8]) -> Result<Escrow> {
+    Escrow::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_config(data: &[u8]) -> Result<Config> {
+    Config::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_receipt(data: &[u8]) -> Result<Receipt> {
+    Receipt::try_deserialize(&mut &data[..])
+}
@@ -0,0 +1,69 @@
+//! Snapshots accounts from a cluster into `fixtures/` so the LiteSVM tests can load them offline.
+//!
+//! cargo run --example capture_fixture -- <address>... [--url <rpc url>] [--name <name>]
+//!
+//! Each account is written to `fixtures/<address>.json`, or `fixtures/<name>.json` when a single
+//! address is captured with `--name`, in the same layout as `solana account <address> --output
+//! json` with the data base64 encoded. Tests load it with `read_fixture("<address>")` or
+//! `read_fixture("<name>")`.
+
+use {
+    serde_json::json,
+    solana_account_decoder_client_types::UiAccount,
+    solana_rpc_client::rpc_client::RpcClient,
+    solana_rpc_client_api::{
+        request::RpcRequest,
+        response::{Response, RpcKeyedAccount},
+    },
+    std::path::PathBuf,
+};
+
+const DEFAULT_URL: &str = "https://api.devnet.solana.com";
+
+fn main() {
+    let mut url = DEFAULT_URL.to_string();
+    let mut name = None;
+    let mut addresses = vec![];
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--url" => url = args.next().expect("--url needs a value"),
+            "--name" => name = Some(args.next().expect("--name needs a value")),
+            _ => addresses.push(arg),
+        }
+    }
+    assert!(
+        !addresses.is_empty(),
+        "usage: capture_fixture <address>... [--url <rpc url>] [--name <name>]"
+    );
+    assert!(
+        name.is_none() || addresses.len() == 1,
+        "--name can only be given for a single address"
+    );
+
+    let rpc_client = RpcClient::new(url);
+    let fixtures_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+    std::fs::create_dir_all(&fixtures_dir).expect("Failed to create fixtures directory");
+
+    for address in addresses {
+        // ask for base64 so the data comes back byte for byte, never jsonParsed
+        let response: Response<Option<UiAccount>> = rpc_client
+            .send(
+                RpcRequest::GetAccountInfo,
+                json!([address, { "encoding": "base64" }]),
+            )
+            .expect("Failed to fetch account");
+        let account = response
+            .value
+            .unwrap_or_else(|| panic!("Account {address} does not exist"));
+
+        let path = fixtures_dir.join(format!("{}.json", name.as_deref().unwrap_or(&address)));
+        let fixture = RpcKeyedAccount {
+            pubkey: address,
+            account,
+        };
+        std::fs::write(&path, serde_json::to_string_pretty(&fixture).unwrap())
+            .expect("Failed to write fixture");
+        println!("Captured {}", path.display());
+    }
+}
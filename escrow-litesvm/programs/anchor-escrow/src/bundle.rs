@@ -0,0 +1,135 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::get_associated_token_address_with_program_id,
+    token_interface::{close_account, CloseAccount, Mint, TokenAccount, TransferChecked},
+};
+
+use crate::{
+    state::BundleLeg,
+    utils::{init_ata_if_needed, transfer_checked_with_extensions},
+    EscrowError,
+};
+
+// every bundle leg is passed as `[mint, from, to]` at the front of the remaining accounts
+pub const BUNDLE_LEG_ACCOUNTS: usize = 3;
+// a bundle_b leg paid on take also carries the fee recipient's token account, `[mint, from, to, treasury]`
+pub const BUNDLE_B_LEG_ACCOUNTS: usize = 4;
+
+/// splits the remaining accounts into the bundle legs and the transfer-hook extra accounts after them,
+/// `fee_legs` are laid out as bundle_b legs paid on take
+pub fn split_bundle_accounts<'a, 'info>(
+    remaining_accounts: &'a [AccountInfo<'info>],
+    legs: usize,
+    fee_legs: usize,
+) -> Result<(&'a [AccountInfo<'info>], &'a [AccountInfo<'info>])> {
+    let len = legs * BUNDLE_LEG_ACCOUNTS + fee_legs * BUNDLE_B_LEG_ACCOUNTS;
+    require!(
+        remaining_accounts.len() >= len,
+        EscrowError::InvalidBundleAccounts
+    );
+    Ok(remaining_accounts.split_at(len))
+}
+
+/// programs, payer and signer shared by every leg moved in one instruction
+pub struct BundleContext<'a, 'info> {
+    pub token_program: AccountInfo<'info>,
+    pub associated_token_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    // funds the destination token account when it does not exist yet
+    pub payer: AccountInfo<'info>,
+    pub signer_seeds: &'a [&'a [&'a [u8]]],
+    pub extra_accounts: &'a [AccountInfo<'info>],
+}
+
+impl<'info> BundleContext<'_, 'info> {
+    /// moves `amount` of the leg from `from_authority`'s token account to `to_owner`'s, the whole
+    /// source balance when `amount` is None, and returns the amount moved
+    pub fn move_leg(
+        &self,
+        leg: &BundleLeg,
+        accounts: &[AccountInfo<'info>],
+        from_authority: &AccountInfo<'info>,
+        to_owner: &AccountInfo<'info>,
+        amount: Option<u64>,
+    ) -> Result<u64> {
+        let [mint, from, to] = accounts else {
+            return err!(EscrowError::InvalidBundleAccounts);
+        };
+        require_keys_eq!(mint.key(), leg.mint, EscrowError::InvalidBundleAccounts);
+        require_keys_eq!(
+            from.key(),
+            self.ata(from_authority.key, mint.key),
+            EscrowError::InvalidBundleAccounts
+        );
+        require_keys_eq!(
+            to.key(),
+            self.ata(to_owner.key, mint.key),
+            EscrowError::InvalidBundleAccounts
+        );
+
+        init_ata_if_needed(
+            to.clone(),
+            to_owner.clone(),
+            mint.clone(),
+            self.payer.clone(),
+            self.associated_token_program.clone(),
+            self.token_program.clone(),
+            self.system_program.clone(),
+        )?;
+
+        // the token program rejects a mint or source account it does not own
+        require_keys_eq!(
+            *mint.owner,
+            self.token_program.key(),
+            EscrowError::InvalidBundleAccounts
+        );
+        let decimals = Mint::try_deserialize(&mut &mint.try_borrow_data()?[..])?.decimals;
+        let amount = match amount {
+            Some(amount) => amount,
+            None => TokenAccount::try_deserialize(&mut &from.try_borrow_data()?[..])?.amount,
+        };
+
+        let cpi_accounts = TransferChecked {
+            from: from.clone(),
+            mint: mint.clone(),
+            to: to.clone(),
+            authority: from_authority.clone(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.clone(),
+            cpi_accounts,
+            self.signer_seeds,
+        )
+        .with_remaining_accounts(self.extra_accounts.to_vec());
+
+        transfer_checked_with_extensions(cpi_ctx, amount, decimals)?;
+
+        Ok(amount)
+    }
+
+    /// closes the emptied vault of a leg, `accounts` laid out as for `move_leg`
+    pub fn close_vault(
+        &self,
+        accounts: &[AccountInfo<'info>],
+        vault_index: usize,
+        authority: &AccountInfo<'info>,
+        destination: &AccountInfo<'info>,
+    ) -> Result<()> {
+        let cpi_accounts = CloseAccount {
+            account: accounts[vault_index].clone(),
+            destination: destination.clone(),
+            authority: authority.clone(),
+        };
+
+        close_account(CpiContext::new_with_signer(
+            self.token_program.clone(),
+            cpi_accounts,
+            self.signer_seeds,
+        ))
+    }
+
+    fn ata(&self, owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(owner, mint, self.token_program.key)
+    }
+}
@@ -0,0 +1,140 @@
+use anchor_lang::prelude::*;
+
+#[event]
+pub struct ConfigUpdated {
+    pub admin: Pubkey,
+    pub fee_bps: u16,
+    pub fee_recipient: Pubkey,
+    pub paused: bool,
+}
+
+#[event]
+pub struct EscrowMade {
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub deposited: u64,
+    pub receive: u64,
+}
+
+#[event]
+pub struct EscrowTaken {
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    // mint a released from the vault
+    pub amount_a: u64,
+    // mint b of the offer filled, before protocol and transfer fees
+    pub amount_b: u64,
+    pub remaining_receive: u64,
+}
+
+#[event]
+pub struct EscrowRefunded {
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub mint_a: Pubkey,
+    pub amount_a: u64,
+}
+
+#[event]
+pub struct EscrowCrankRefunded {
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub cranker: Pubkey,
+    pub mint_a: Pubkey,
+    pub amount_a: u64,
+    // lamports paid to the cranker
+    pub tip: u64,
+}
+
+#[event]
+pub struct EscrowFunded {
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub amount_b: u64,
+}
+
+#[event]
+pub struct EscrowDisputed {
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub raised_by: Pubkey,
+}
+
+#[event]
+pub struct EscrowReleased {
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub released_by: Pubkey,
+    pub taker_bps: u16,
+    // mint a sent to the taker, the rest of the vault returned to the maker
+    pub amount_a: u64,
+    // mint b sent to the maker, the rest of vault_b returned to the taker
+    pub amount_b: u64,
+}
+
+#[event]
+pub struct EscrowAmended {
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub deposited: u64,
+    pub receive: u64,
+    pub revision: u64,
+}
+
+#[event]
+pub struct EscrowContributed {
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub amount_b: u64,
+    // total mint b pooled so far, the escrow is filled once it reaches `receive`
+    pub contributed: u64,
+}
+
+#[event]
+pub struct EscrowClaimed {
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    // share of the vault paid out for the taker's `amount_b` contribution
+    pub amount_a: u64,
+    pub amount_b: u64,
+}
+
+#[event]
+pub struct EscrowContributionRefunded {
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub amount_b: u64,
+}
+
+#[event]
+pub struct OrderFilled {
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub nonce: u64,
+    pub amount_a: u64,
+    pub amount_b: u64,
+}
+
+#[event]
+pub struct OrderCancelled {
+    pub maker: Pubkey,
+    pub nonce: u64,
+}
+
+#[event]
+pub struct EscrowMigrated {
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+}
@@ -0,0 +1,139 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked};
+
+use crate::{
+    events::EscrowAmended,
+    state::Escrow,
+    utils::{is_native_mint, transfer_checked_with_extensions, wrap_sol},
+    EscrowError,
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct Amend<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+    )]
+    pub maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        has_one = mint_a,
+        has_one = maker,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+//Top up the vault from maker
+//Withdraw part of the vault back to maker, SOL offers withdraw as wrapped SOL since maker_ata_a stays open
+//Update the asked amount and bump the revision takers can guard against
+impl<'info> Amend<'info> {
+    pub fn top_up(&mut self, amount: u64, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        if is_native_mint(&self.mint_a.key()) {
+            wrap_sol(
+                self.system_program.to_account_info(),
+                self.token_program.to_account_info(),
+                self.maker.to_account_info(),
+                self.maker_ata_a.to_account_info(),
+                amount,
+            )?;
+        }
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.maker_ata_a.to_account_info(),
+            to: self.vault.to_account_info(),
+            authority: self.maker.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+
+        transfer_checked_with_extensions(cpi_ctx, amount, self.mint_a.decimals)
+    }
+
+    pub fn withdraw(
+        &mut self,
+        amount: u64,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        // pulling tokens out is a partial refund, so the refund lock applies
+        self.escrow
+            .ensure_can_refund(Clock::get()?.unix_timestamp)?;
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.maker.key.as_ref(),
+            &self.escrow.seed.to_le_bytes()[..],
+            &[self.escrow.bump],
+        ]];
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            to: self.maker_ata_a.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+
+        transfer_checked_with_extensions(cpi_context, amount, self.mint_a.decimals)
+    }
+
+    pub fn update_terms(&mut self, receive: u64) -> Result<()> {
+        self.escrow.ensure_unlocked()?;
+        self.vault.reload()?;
+        require!(
+            receive > 0 && self.vault.amount > 0,
+            EscrowError::InvalidAmendment
+        );
+
+        self.escrow.receive = receive;
+        self.escrow.validate_auction()?;
+        self.escrow.deposited = self.vault.amount;
+        self.escrow.revision = self
+            .escrow
+            .revision
+            .checked_add(1)
+            .ok_or(EscrowError::MathOverflow)?;
+
+        Ok(())
+    }
+
+    pub fn amended_event(&self) -> EscrowAmended {
+        EscrowAmended {
+            seed: self.escrow.seed,
+            maker: self.escrow.maker,
+            deposited: self.escrow.deposited,
+            receive: self.escrow.receive,
+            revision: self.escrow.revision,
+        }
+    }
+}
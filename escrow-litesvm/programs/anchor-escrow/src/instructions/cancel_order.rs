@@ -0,0 +1,40 @@
+use anchor_lang::prelude::*;
+
+use crate::{events::OrderCancelled, state::OrderNonce};
+
+//Maker spends an order nonce so any order signed with it can no longer be filled
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(nonce: u64)]
+pub struct CancelOrder<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    #[account(
+        init,
+        payer = maker,
+        space = 8 + OrderNonce::INIT_SPACE,
+        seeds = [b"order", maker.key().as_ref(), nonce.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub order_nonce: Account<'info, OrderNonce>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CancelOrder<'info> {
+    pub fn cancel_order(&mut self, nonce: u64, bumps: &CancelOrderBumps) -> Result<()> {
+        self.order_nonce.set_inner(OrderNonce {
+            maker: self.maker.key(),
+            nonce,
+            cancelled: true,
+            bump: bumps.order_nonce,
+        });
+        Ok(())
+    }
+
+    pub fn cancelled_event(&self) -> OrderCancelled {
+        OrderCancelled {
+            maker: self.maker.key(),
+            nonce: self.order_nonce.nonce,
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+    },
+};
+
+use crate::{
+    events::EscrowClaimed,
+    state::{Escrow, Receipt},
+    utils::transfer_checked_with_extensions,
+    EscrowError,
+};
+
+//Contributor's pro-rata share of a filled crowd escrow
+//the last claim empties and closes the vault and the escrow
+#[event_cpi]
+#[derive(Accounts)]
+pub struct Claim<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_a,
+        associated_token::authority = taker,
+    )]
+    pub taker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        has_one = maker,
+        has_one = mint_a,
+        constraint = escrow.is_crowd_filled() @ EscrowError::CrowdNotFilled,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        close = taker,
+        has_one = escrow,
+        has_one = taker,
+        seeds = [b"receipt", escrow.key().as_ref(), taker.key().as_ref()],
+        bump = receipt.bump,
+    )]
+    pub receipt: Account<'info, Receipt>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Claim<'info> {
+    pub fn claim_and_close_if_done(
+        &mut self,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<u64> {
+        let outstanding = self.escrow.receive - self.escrow.claimed;
+        let share = Escrow::crowd_share(self.vault.amount, self.receipt.amount, outstanding)?;
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.maker.key.as_ref(),
+            &self.escrow.seed.to_le_bytes()[..],
+            &[self.escrow.bump],
+        ]];
+
+        if share > 0 {
+            let cpi_program = self.token_program.to_account_info();
+
+            let cpi_accounts = TransferChecked {
+                from: self.vault.to_account_info(),
+                to: self.taker_ata_a.to_account_info(),
+                authority: self.escrow.to_account_info(),
+                mint: self.mint_a.to_account_info(),
+            };
+
+            let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds)
+                .with_remaining_accounts(remaining_accounts.to_vec());
+
+            transfer_checked_with_extensions(cpi_context, share, self.mint_a.decimals)?;
+        }
+
+        self.escrow.claimed += self.receipt.amount;
+
+        if self.escrow.claimed < self.escrow.receive {
+            return Ok(share);
+        }
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = CloseAccount {
+            account: self.vault.to_account_info(),
+            destination: self.maker.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        close_account(cpi_context)?;
+
+        self.escrow.close(self.maker.to_account_info())?;
+
+        Ok(share)
+    }
+
+    pub fn claimed_event(&self, amount_a: u64) -> EscrowClaimed {
+        EscrowClaimed {
+            seed: self.escrow.seed,
+            maker: self.escrow.maker,
+            taker: self.taker.key(),
+            amount_a,
+            amount_b: self.receipt.amount,
+        }
+    }
+}
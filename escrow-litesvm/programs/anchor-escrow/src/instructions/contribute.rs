@@ -0,0 +1,205 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+    },
+};
+
+use crate::{
+    events::EscrowContributed,
+    state::{Config, Escrow, Receipt},
+    utils::{is_native_mint, transfer_checked_with_extensions, wrap_sol},
+    EscrowError,
+};
+
+//Taker side of a crowd-filled escrow
+//each contribution is pooled in vault_b and recorded on the taker's receipt, SOL is wrapped from
+//the taker's lamports on the way in
+//the contribution that fills the offer pays vault_b out to the maker, less the protocol fee
+#[event_cpi]
+#[derive(Accounts)]
+pub struct Contribute<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = taker,
+    )]
+    pub taker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = maker,
+    )]
+    pub maker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        has_one = maker,
+        has_one = mint_b,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Box<Account<'info, Escrow>>,
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = escrow,
+    )]
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = taker,
+        space = 8 + Receipt::INIT_SPACE,
+        seeds = [b"receipt", escrow.key().as_ref(), taker.key().as_ref()],
+        bump,
+    )]
+    pub receipt: Box<Account<'info, Receipt>>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = fee_recipient,
+        constraint = !config.paused @ EscrowError::ProgramPaused,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(mut)]
+    pub fee_recipient: SystemAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = fee_recipient,
+    )]
+    pub treasury_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Contribute<'info> {
+    pub fn ensure_can_contribute(&self, amount: u64) -> Result<()> {
+        self.escrow
+            .ensure_can_contribute(Clock::get()?.unix_timestamp, amount)
+    }
+
+    // the maker can still amend the ask until the first contribution lands
+    pub fn ensure_revision(&self, expected_revision: Option<u64>) -> Result<()> {
+        self.escrow.ensure_revision(expected_revision)
+    }
+
+    pub fn contribute(
+        &mut self,
+        amount: u64,
+        remaining_accounts: &[AccountInfo<'info>],
+        bumps: &ContributeBumps,
+    ) -> Result<()> {
+        if is_native_mint(&self.mint_b.key()) {
+            wrap_sol(
+                self.system_program.to_account_info(),
+                self.token_program.to_account_info(),
+                self.taker.to_account_info(),
+                self.taker_ata_b.to_account_info(),
+                amount,
+            )?;
+        }
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.taker_ata_b.to_account_info(),
+            to: self.vault_b.to_account_info(),
+            authority: self.taker.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+
+        transfer_checked_with_extensions(cpi_ctx, amount, self.mint_b.decimals)?;
+
+        self.receipt.escrow = self.escrow.key();
+        self.receipt.taker = self.taker.key();
+        self.receipt.bump = bumps.receipt;
+        self.receipt.amount = self
+            .receipt
+            .amount
+            .checked_add(amount)
+            .ok_or(EscrowError::MathOverflow)?;
+        self.escrow.contributed = self
+            .escrow
+            .contributed
+            .checked_add(amount)
+            .ok_or(EscrowError::MathOverflow)?;
+
+        Ok(())
+    }
+
+    // vault_b holds the pool net of any mint b transfer fee, the fee is taken on what it holds
+    pub fn pay_maker_if_filled(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        if !self.escrow.is_crowd_filled() {
+            return Ok(());
+        }
+
+        self.vault_b.reload()?;
+        let fee = self.config.fee_for(self.vault_b.amount)?;
+        let to_maker = self.vault_b.amount - fee;
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.maker.key.as_ref(),
+            &self.escrow.seed.to_le_bytes()[..],
+            &[self.escrow.bump],
+        ]];
+
+        for (to, amount) in [(&self.maker_ata_b, to_maker), (&self.treasury_ata_b, fee)] {
+            if amount == 0 {
+                continue;
+            }
+
+            let cpi_accounts = TransferChecked {
+                from: self.vault_b.to_account_info(),
+                to: to.to_account_info(),
+                authority: self.escrow.to_account_info(),
+                mint: self.mint_b.to_account_info(),
+            };
+
+            let cpi_ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                cpi_accounts,
+                &signer_seeds,
+            )
+            .with_remaining_accounts(remaining_accounts.to_vec());
+
+            transfer_checked_with_extensions(cpi_ctx, amount, self.mint_b.decimals)?;
+        }
+
+        let cpi_accounts = CloseAccount {
+            account: self.vault_b.to_account_info(),
+            destination: self.maker.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+
+        close_account(CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            &signer_seeds,
+        ))
+    }
+
+    pub fn contributed_event(&self, amount: u64) -> EscrowContributed {
+        EscrowContributed {
+            seed: self.escrow.seed,
+            maker: self.escrow.maker,
+            taker: self.taker.key(),
+            amount_b: amount,
+            contributed: self.escrow.contributed,
+        }
+    }
+}
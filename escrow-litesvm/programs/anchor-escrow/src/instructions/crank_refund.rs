@@ -0,0 +1,89 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{events::EscrowCrankRefunded, instructions::VaultRefund, state::Escrow};
+
+//Permissionless refund of an expired offer
+//cranker pays the maker_ata_a rent if the maker closed it, and is paid the maker's crank tip
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CrankRefund<'info> {
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = cranker,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+    )]
+    pub maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        close = maker,
+        has_one = mint_a,
+        has_one = maker,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CrankRefund<'info> {
+    pub fn ensure_expired(&self) -> Result<()> {
+        self.escrow.ensure_expired(Clock::get()?.unix_timestamp)
+    }
+
+    pub fn vault_refund(&self) -> VaultRefund<'_, 'info> {
+        VaultRefund {
+            maker: self.maker.to_account_info(),
+            mint_a: &self.mint_a,
+            maker_ata_a: self.maker_ata_a.to_account_info(),
+            escrow: &self.escrow,
+            vault: &self.vault,
+            payer: self.cranker.to_account_info(),
+            associated_token_program: self.associated_token_program.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+        }
+    }
+
+    // the tip sits on the escrow above its rent, whatever is left goes to the maker on close
+    pub fn pay_tip(&mut self) -> Result<()> {
+        let tip = self.escrow.crank_tip;
+        if tip == 0 {
+            return Ok(());
+        }
+
+        self.escrow.sub_lamports(tip)?;
+        self.cranker.add_lamports(tip)?;
+
+        Ok(())
+    }
+
+    // vault balance is not reloaded after the transfer, so it still holds the refunded amount
+    pub fn crank_refunded_event(&self) -> EscrowCrankRefunded {
+        EscrowCrankRefunded {
+            seed: self.escrow.seed,
+            maker: self.escrow.maker,
+            cranker: self.cranker.key(),
+            mint_a: self.escrow.mint_a,
+            amount_a: self.vault.amount,
+            tip: self.escrow.crank_tip,
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use anchor_lang::{prelude::*, solana_program::sysvar};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    events::OrderFilled,
+    signature::verify_ed25519_signature,
+    state::{Config, Order, OrderNonce},
+    utils::transfer_checked_with_extensions,
+    EscrowError,
+};
+
+//Settle an order the maker signed off-chain, nothing is locked up front
+//the maker approves the delegate PDA on maker_ata_a for at least `amount_a`, the taker sends
+//an Ed25519 program instruction carrying the maker's signature over `order.message()` right
+//before this one
+//both legs move in this instruction, the order nonce PDA is created so it can't fill twice
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(order: Order)]
+pub struct FillOrder<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    #[account(address = order.maker @ EscrowError::InvalidOrder)]
+    pub maker: SystemAccount<'info>,
+    #[account(address = order.mint_a @ EscrowError::InvalidOrder)]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+    #[account(address = order.mint_b @ EscrowError::InvalidOrder)]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+    )]
+    pub maker_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = maker,
+    )]
+    pub maker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_a,
+        associated_token::authority = taker,
+    )]
+    pub taker_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = taker,
+    )]
+    pub taker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// CHECK: signing-only PDA the maker approves on maker_ata_a
+    #[account(seeds = [b"delegate"], bump)]
+    pub delegate: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = taker,
+        space = 8 + OrderNonce::INIT_SPACE,
+        seeds = [b"order", maker.key().as_ref(), order.nonce.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub order_nonce: Box<Account<'info, OrderNonce>>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = fee_recipient,
+        constraint = !config.paused @ EscrowError::ProgramPaused,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(mut)]
+    pub fee_recipient: SystemAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = fee_recipient,
+    )]
+    pub treasury_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// CHECK: instructions sysvar, read for the Ed25519 signature check
+    #[account(address = sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> FillOrder<'info> {
+    pub fn ensure_can_fill(&self, order: &Order) -> Result<()> {
+        order.ensure_can_fill(Clock::get()?.unix_timestamp, self.taker.key)?;
+        verify_ed25519_signature(&self.instructions, &order.maker, &order.message()?)
+    }
+
+    pub fn fill(
+        &mut self,
+        order: &Order,
+        remaining_accounts: &[AccountInfo<'info>],
+        bumps: &FillOrderBumps,
+    ) -> Result<()> {
+        self.order_nonce.set_inner(OrderNonce {
+            maker: order.maker,
+            nonce: order.nonce,
+            cancelled: false,
+            bump: bumps.order_nonce,
+        });
+
+        let fee = self.config.fee_for(order.amount_b)?;
+        let to_maker = order.amount_b - fee;
+
+        for (to, amount) in [(&self.maker_ata_b, to_maker), (&self.treasury_ata_b, fee)] {
+            if amount == 0 {
+                continue;
+            }
+
+            let cpi_accounts = TransferChecked {
+                from: self.taker_ata_b.to_account_info(),
+                to: to.to_account_info(),
+                authority: self.taker.to_account_info(),
+                mint: self.mint_b.to_account_info(),
+            };
+
+            let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+                .with_remaining_accounts(remaining_accounts.to_vec());
+
+            transfer_checked_with_extensions(cpi_ctx, amount, self.mint_b.decimals)?;
+        }
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[b"delegate", &[bumps.delegate]]];
+
+        let cpi_accounts = TransferChecked {
+            from: self.maker_ata_a.to_account_info(),
+            to: self.taker_ata_a.to_account_info(),
+            authority: self.delegate.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            &signer_seeds,
+        )
+        .with_remaining_accounts(remaining_accounts.to_vec());
+
+        transfer_checked_with_extensions(cpi_ctx, order.amount_a, self.mint_a.decimals)
+    }
+
+    pub fn filled_event(&self, order: &Order) -> OrderFilled {
+        OrderFilled {
+            maker: order.maker,
+            taker: self.taker.key(),
+            nonce: order.nonce,
+            amount_a: order.amount_a,
+            amount_b: order.amount_b,
+        }
+    }
+}
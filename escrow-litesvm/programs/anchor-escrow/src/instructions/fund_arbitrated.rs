@@ -0,0 +1,123 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    events::EscrowFunded,
+    state::{Config, Escrow},
+    utils::{is_native_mint, net_of_fee, transfer_checked_with_extensions, wrap_sol},
+    EscrowError,
+};
+
+//Taker side of an arbitrated escrow
+//mint b is locked in vault_b next to the maker's vault until the escrow is released
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FundArbitrated<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    pub maker: SystemAccount<'info>,
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    pub mint_b: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = taker,
+    )]
+    pub taker_ata_b: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        has_one = maker,
+        has_one = mint_a,
+        has_one = mint_b,
+        constraint = escrow.can_be_taken_by(taker.key) @ EscrowError::UnauthorizedTaker,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = escrow,
+    )]
+    pub vault_b: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ EscrowError::ProgramPaused,
+    )]
+    pub config: Account<'info, Config>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> FundArbitrated<'info> {
+    pub fn ensure_can_fund(&self) -> Result<()> {
+        self.escrow.ensure_can_fund(Clock::get()?.unix_timestamp)
+    }
+
+    pub fn ensure_revision(&self, expected_revision: Option<u64>) -> Result<()> {
+        self.escrow.ensure_revision(expected_revision)
+    }
+
+    // taker locks at most `expected_receive` against at least `min_amount_a` in the vault,
+    // after the mint a transfer fee, so an amend landing first can't change the deal
+    pub fn ensure_slippage(&self, expected_receive: u64, min_amount_a: u64) -> Result<()> {
+        let amount_a = net_of_fee(&self.mint_a.to_account_info(), self.vault.amount)?;
+        require!(
+            self.escrow.receive <= expected_receive && amount_a >= min_amount_a,
+            EscrowError::SlippageExceeded
+        );
+        Ok(())
+    }
+
+    pub fn deposit(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        if is_native_mint(&self.mint_b.key()) {
+            wrap_sol(
+                self.system_program.to_account_info(),
+                self.token_program.to_account_info(),
+                self.taker.to_account_info(),
+                self.taker_ata_b.to_account_info(),
+                self.escrow.receive,
+            )?;
+        }
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.taker_ata_b.to_account_info(),
+            to: self.vault_b.to_account_info(),
+            authority: self.taker.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+
+        transfer_checked_with_extensions(cpi_ctx, self.escrow.receive, self.mint_b.decimals)?;
+
+        self.vault_b.reload()?;
+        self.escrow.taker = Some(self.taker.key());
+
+        Ok(())
+    }
+
+    pub fn funded_event(&self) -> EscrowFunded {
+        EscrowFunded {
+            seed: self.escrow.seed,
+            maker: self.escrow.maker,
+            taker: self.taker.key(),
+            amount_b: self.vault_b.amount,
+        }
+    }
+}
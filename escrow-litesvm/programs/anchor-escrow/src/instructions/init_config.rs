@@ -0,0 +1,63 @@
+use anchor_lang::{prelude::*, solana_program::bpf_loader_upgradeable};
+
+use crate::{
+    events::ConfigUpdated,
+    state::{Config, MAX_FEE_BPS},
+    EscrowError,
+};
+
+//Only the program's upgrade authority may create the config and become its first admin,
+//so the singleton can't be claimed by whoever front-runs the deploy
+#[event_cpi]
+#[derive(Accounts)]
+pub struct InitConfig<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"config"],
+        bump,
+        space = 8 + Config::INIT_SPACE,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(admin.key())
+            @ EscrowError::UnauthorizedAdmin,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitConfig<'info> {
+    pub fn init_config(
+        &mut self,
+        fee_bps: u16,
+        fee_recipient: Pubkey,
+        bumps: &InitConfigBumps,
+    ) -> Result<()> {
+        require!(fee_bps <= MAX_FEE_BPS, EscrowError::InvalidFee);
+
+        self.config.set_inner(Config {
+            admin: self.admin.key(),
+            fee_bps,
+            fee_recipient,
+            paused: false,
+            bump: bumps.config,
+        });
+
+        Ok(())
+    }
+
+    pub fn config_updated_event(&self) -> ConfigUpdated {
+        ConfigUpdated {
+            admin: self.config.admin,
+            fee_bps: self.config.fee_bps,
+            fee_recipient: self.config.fee_recipient,
+            paused: self.config.paused,
+        }
+    }
+}
@@ -1,11 +1,47 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    bundle::{BundleContext, BUNDLE_LEG_ACCOUNTS},
+    events::EscrowMade,
+    state::{BundleLeg, Config, Escrow, ESCROW_VERSION},
+    utils::{is_native_mint, transfer_checked_with_extensions, wrap_sol},
+    EscrowError,
 };
 
-use crate::state::Escrow;
+// optional terms of an offer, timestamps are unix seconds and 0 leaves the bound unset
+// a non-zero auction_end turns `receive` into the starting price of a dutch auction
+// crank_tip is paid in lamports to whoever refunds the offer once it has expired
+// hashlock makes take reveal its sha256 preimage, expires_at is then required as the timeout
+// and allowed_taker as the counterparty, so nobody else can take with the revealed preimage
+// arbiter turns the offer into an arbitrated escrow settled by fund_arbitrated and release
+// bundle_a and bundle_b add extra mints deposited and asked for next to mint a and mint b
+// crowd lets several takers pool `receive` and claim the vault pro rata, expires_at is required
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default)]
+pub struct MakeArgs {
+    pub take_not_before: i64,
+    pub expires_at: i64,
+    pub refund_not_before: i64,
+    pub allowed_taker: Option<Pubkey>,
+    pub receive_is_net: bool,
+    pub end_receive: u64,
+    pub auction_start: i64,
+    pub auction_end: i64,
+    pub crank_tip: u64,
+    pub hashlock: Option<[u8; 32]>,
+    pub arbiter: Option<Pubkey>,
+    pub bundle_a: Vec<BundleLeg>,
+    pub bundle_b: Vec<BundleLeg>,
+    pub crowd: bool,
+}
 
+#[event_cpi]
 #[derive(Accounts)]
 #[instruction(seed: u64)]
 pub struct Make<'info> {
@@ -14,7 +50,8 @@ pub struct Make<'info> {
     pub mint_a: InterfaceAccount<'info, Mint>,
     pub mint_b: InterfaceAccount<'info, Mint>,
     #[account(
-        mut,
+        init_if_needed,
+        payer = maker,
         associated_token::mint = mint_a,
         associated_token::authority = maker,
     )]
@@ -34,27 +71,81 @@ pub struct Make<'info> {
         associated_token::authority = escrow,
     )]
     pub vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ EscrowError::ProgramPaused,
+    )]
+    pub config: Account<'info, Config>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> Make<'info> {
-    pub fn init_escrow(&mut self, seed: u64, receive: u64, bumps: &MakeBumps) -> Result<()> {
+    pub fn init_escrow(
+        &mut self,
+        seed: u64,
+        receive: u64,
+        args: MakeArgs,
+        bumps: &MakeBumps,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+
         self.escrow.set_inner(Escrow {
+            version: ESCROW_VERSION,
             seed,
             maker: self.maker.key(),
             mint_a: self.mint_a.key(),
             mint_b: self.mint_b.key(),
             receive,
             bump: bumps.escrow,
-            initialization_time: Clock::get()?.unix_timestamp as u64,
+            initialization_time: now as u64,
+            take_not_before: args.take_not_before,
+            expires_at: args.expires_at,
+            refund_not_before: args.refund_not_before,
+            allowed_taker: args.allowed_taker,
+            receive_is_net: args.receive_is_net,
+            deposited: 0,
+            revision: 0,
+            end_receive: args.end_receive,
+            auction_start: args.auction_start,
+            auction_end: args.auction_end,
+            crank_tip: args.crank_tip,
+            hashlock: args.hashlock,
+            arbiter: args.arbiter,
+            taker: None,
+            disputed: false,
+            bundle_a: args.bundle_a,
+            bundle_b: args.bundle_b,
+            crowd: args.crowd,
+            contributed: 0,
+            claimed: 0,
         });
 
-        Ok(())
+        self.escrow.validate_time_policy(now)?;
+        self.escrow.validate_auction()?;
+        self.escrow.validate_bundle()?;
+        self.escrow.validate_crowd()?;
+        self.escrow.validate_arbiter()
     }
 
-    pub fn deposit(&mut self, deposit: u64) -> Result<()> {
+    pub fn deposit(
+        &mut self,
+        deposit: u64,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        // SOL offers are wrapped from the maker's lamports on the way in
+        if is_native_mint(&self.mint_a.key()) {
+            wrap_sol(
+                self.system_program.to_account_info(),
+                self.token_program.to_account_info(),
+                self.maker.to_account_info(),
+                self.maker_ata_a.to_account_info(),
+                deposit,
+            )?;
+        }
+
         let cpi_program = self.token_program.to_account_info();
 
         let cpi_accounts = TransferChecked {
@@ -64,10 +155,72 @@ impl<'info> Make<'info> {
             mint: self.mint_a.to_account_info(),
         };
 
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+
+        transfer_checked_with_extensions(cpi_ctx, deposit, self.mint_a.decimals)?;
+
+        // record what actually landed in the vault after any mint transfer fee
+        self.vault.reload()?;
+        self.escrow.deposited = self.vault.amount;
 
-        transfer_checked(cpi_ctx, deposit, self.mint_a.decimals)?;
+        Ok(())
+    }
+
+    // bundle accounts are `[mint, maker ata, vault]` per bundle_a leg
+    pub fn deposit_bundle(
+        &mut self,
+        bundle_accounts: &[AccountInfo<'info>],
+        extra_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let bundle = BundleContext {
+            token_program: self.token_program.to_account_info(),
+            associated_token_program: self.associated_token_program.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+            payer: self.maker.to_account_info(),
+            signer_seeds: &[],
+            extra_accounts,
+        };
+
+        let maker = self.maker.to_account_info();
+        let escrow = self.escrow.to_account_info();
+        for (leg, accounts) in self
+            .escrow
+            .bundle_a
+            .iter()
+            .zip(bundle_accounts.chunks_exact(BUNDLE_LEG_ACCOUNTS))
+        {
+            bundle.move_leg(leg, accounts, &maker, &escrow, Some(leg.amount))?;
+        }
 
         Ok(())
     }
+
+    // tip is parked on the escrow itself and returns to the maker on any other close
+    pub fn fund_crank_tip(&mut self) -> Result<()> {
+        if self.escrow.crank_tip == 0 {
+            return Ok(());
+        }
+
+        let cpi_accounts = Transfer {
+            from: self.maker.to_account_info(),
+            to: self.escrow.to_account_info(),
+        };
+
+        transfer(
+            CpiContext::new(self.system_program.to_account_info(), cpi_accounts),
+            self.escrow.crank_tip,
+        )
+    }
+
+    pub fn made_event(&self) -> EscrowMade {
+        EscrowMade {
+            seed: self.escrow.seed,
+            maker: self.escrow.maker,
+            mint_a: self.escrow.mint_a,
+            mint_b: self.escrow.mint_b,
+            deposited: self.escrow.deposited,
+            receive: self.escrow.receive,
+        }
+    }
 }
@@ -0,0 +1,101 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::{
+    associated_token::get_associated_token_address_with_program_id, token_interface::TokenAccount,
+};
+
+use crate::{
+    events::EscrowMigrated,
+    state::{Escrow, EscrowV0, ESCROW_VERSION},
+    EscrowError,
+};
+
+//Rewrite an escrow left by an older program version in the current layout, in place
+//the account is reallocated to the current size, the payer covers the extra rent
+//anyone may run it, the offer terms are carried over unchanged
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MigrateEscrow<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: escrow in any past layout, the discriminator and version are checked by hand
+    #[account(mut, owner = crate::ID)]
+    pub escrow: UncheckedAccount<'info>,
+    // mint a vault of the escrow, its balance becomes `deposited`
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MigrateEscrow<'info> {
+    /// upgrades the escrow and returns the version it was stored as
+    pub fn migrate(&mut self) -> Result<u8> {
+        let data = self.escrow.try_borrow_data()?;
+        require!(
+            data.starts_with(Escrow::DISCRIMINATOR),
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+
+        let from_version = Escrow::stored_version(&data)?;
+        let escrow = match from_version {
+            0 => {
+                let legacy = EscrowV0::deserialize(&mut &data[Escrow::DISCRIMINATOR.len()..])?;
+                self.ensure_vault(&legacy.mint_a)?;
+                legacy.upgrade(self.vault.amount)
+            }
+            ESCROW_VERSION => return err!(EscrowError::EscrowUpToDate),
+            _ => return err!(EscrowError::UnsupportedEscrowVersion),
+        };
+        drop(data);
+
+        self.resize(Escrow::DISCRIMINATOR.len() + Escrow::INIT_SPACE)?;
+
+        let mut data = self.escrow.try_borrow_mut_data()?;
+        escrow.try_serialize(&mut &mut data[..])?;
+
+        Ok(from_version)
+    }
+
+    fn ensure_vault(&self, mint_a: &Pubkey) -> Result<()> {
+        let vault = get_associated_token_address_with_program_id(
+            self.escrow.key,
+            mint_a,
+            self.vault.to_account_info().owner,
+        );
+        require_keys_eq!(self.vault.key(), vault, ErrorCode::ConstraintAssociated);
+        Ok(())
+    }
+
+    fn resize(&self, space: usize) -> Result<()> {
+        let rent = Rent::get()?.minimum_balance(space);
+        let top_up = rent.saturating_sub(self.escrow.lamports());
+
+        if top_up > 0 {
+            let cpi_accounts = Transfer {
+                from: self.payer.to_account_info(),
+                to: self.escrow.to_account_info(),
+            };
+
+            transfer(
+                CpiContext::new(self.system_program.to_account_info(), cpi_accounts),
+                top_up,
+            )?;
+        }
+
+        self.escrow.realloc(space, true)?;
+
+        Ok(())
+    }
+
+    pub fn migrated_event(&self, from_version: u8) -> Result<EscrowMigrated> {
+        let escrow = Escrow::try_deserialize(&mut &self.escrow.try_borrow_data()?[..])?;
+
+        Ok(EscrowMigrated {
+            seed: escrow.seed,
+            maker: escrow.maker,
+            from_version,
+            to_version: escrow.version,
+        })
+    }
+}
@@ -1,20 +1,110 @@
+pub mod amend;
+pub mod cancel_order;
+pub mod claim;
+pub mod contribute;
+pub mod crank_refund;
+pub mod fill_order;
+pub mod fund_arbitrated;
+pub mod init_config;
 pub mod make;
+pub mod migrate_escrow;
+pub mod raise_dispute;
 pub mod refund;
-pub mod refund_before_time;
+pub mod refund_contribution;
+pub mod release;
 pub mod take;
-pub mod take_after_time;
+pub mod take_partial;
+pub mod update_config;
 
+pub use amend::*;
 use anchor_lang::error_code;
+pub use cancel_order::*;
+pub use claim::*;
+pub use contribute::*;
+pub use crank_refund::*;
+pub use fill_order::*;
+pub use fund_arbitrated::*;
+pub use init_config::*;
 pub use make::*;
+pub use migrate_escrow::*;
+pub use raise_dispute::*;
 pub use refund::*;
-pub use refund_before_time::*;
+pub use refund_contribution::*;
+pub use release::*;
 pub use take::*;
-pub use take_after_time::*;
+pub use take_partial::*;
+pub use update_config::*;
 
 #[error_code]
 pub enum EscrowError {
     #[msg("Too early to take offer!")]
     TooEarlyToTakeOffer,
-    #[msg("Too late to refund and withdraw offer!")]
-    TooLateToRefund,
+    #[msg("Too early to refund and withdraw offer!")]
+    TooEarlyToRefund,
+    #[msg("Offer has expired!")]
+    OfferExpired,
+    #[msg("Invalid time window for offer!")]
+    InvalidTimeWindow,
+    #[msg("Offer is reserved for another taker!")]
+    UnauthorizedTaker,
+    #[msg("Take amount must be non-zero and within the remaining offer!")]
+    InvalidTakeAmount,
+    #[msg("Arithmetic overflow!")]
+    MathOverflow,
+    #[msg("Fee must not exceed 10000 basis points!")]
+    InvalidFee,
+    #[msg("Escrow program is paused!")]
+    ProgramPaused,
+    #[msg("Amendment must leave a non-zero vault and receive amount!")]
+    InvalidAmendment,
+    #[msg("Escrow revision does not match the expected revision!")]
+    RevisionMismatch,
+    #[msg("Escrow terms moved beyond the taker's slippage bounds!")]
+    SlippageExceeded,
+    #[msg("Auction must run forward in time and decay to a non-zero receive amount!")]
+    InvalidAuction,
+    #[msg("Auction, hashlocked and bundle offers can only be taken in full!")]
+    NotPartiallyFillable,
+    #[msg("Offer has not expired yet!")]
+    OfferNotExpired,
+    #[msg("Preimage does not match the escrow hashlock!")]
+    InvalidPreimage,
+    #[msg("Arbiter must be a third party on a fixed price offer!")]
+    InvalidArbiter,
+    #[msg("Arbitrated escrows settle through fund_arbitrated and release!")]
+    ArbitratedEscrow,
+    #[msg("Escrow is funded by both parties or under dispute!")]
+    EscrowLocked,
+    #[msg("Escrow has not been funded by a taker!")]
+    EscrowNotFunded,
+    #[msg("Signer may not dispute or settle this escrow on these terms!")]
+    UnauthorizedArbitration,
+    #[msg("Split must not exceed 10000 basis points!")]
+    InvalidSplit,
+    #[msg("Bundle legs must be distinct non-zero mints other than the primary mint!")]
+    InvalidBundle,
+    #[msg("Bundle accounts do not match the escrow bundle!")]
+    InvalidBundleAccounts,
+    #[msg("Crowd escrows need an expiry and plain fixed price terms!")]
+    InvalidCrowd,
+    #[msg("Crowd escrows settle through contribute and claim!")]
+    CrowdEscrow,
+    #[msg("Escrow is not crowd-filled!")]
+    NotCrowdEscrow,
+    #[msg("Crowd escrow has not been filled!")]
+    CrowdNotFilled,
+    #[msg("Crowd escrow is already filled!")]
+    CrowdFilled,
+    #[msg("Order does not match the accounts it is filled with!")]
+    InvalidOrder,
+    #[msg("Order is not signed by its maker!")]
+    InvalidOrderSignature,
+    #[msg("Escrow account version is not supported, migrate it first!")]
+    UnsupportedEscrowVersion,
+    #[msg("Escrow is already at the current version!")]
+    EscrowUpToDate,
+    #[msg("Signer is not the program's upgrade authority!")]
+    UnauthorizedAdmin,
+    #[msg("Hashlocked offers must name their taker!")]
+    HashlockWithoutTaker,
 }
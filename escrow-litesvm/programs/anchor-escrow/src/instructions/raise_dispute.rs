@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+
+use crate::{events::EscrowDisputed, state::Escrow, EscrowError};
+
+//Either party freezes a funded arbitrated escrow, only the arbiter can settle it afterwards
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RaiseDispute<'info> {
+    pub party: Signer<'info>,
+    #[account(
+        mut,
+        constraint = escrow.is_party(party.key) @ EscrowError::UnauthorizedArbitration,
+        constraint = escrow.taker.is_some() @ EscrowError::EscrowNotFunded,
+        seeds = [b"escrow", escrow.maker.as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+}
+
+impl<'info> RaiseDispute<'info> {
+    pub fn raise_dispute(&mut self) -> Result<()> {
+        self.escrow.disputed = true;
+        Ok(())
+    }
+
+    pub fn disputed_event(&self) -> EscrowDisputed {
+        EscrowDisputed {
+            seed: self.escrow.seed,
+            maker: self.escrow.maker,
+            raised_by: self.party.key(),
+        }
+    }
+}
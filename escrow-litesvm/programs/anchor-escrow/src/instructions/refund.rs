@@ -1,8 +1,14 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked, CloseAccount, close_account};
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, CloseAccount, close_account}};
 
-use crate::state::Escrow;
+use crate::{
+    bundle::{BundleContext, BUNDLE_LEG_ACCOUNTS},
+    events::EscrowRefunded,
+    state::Escrow,
+    utils::{is_native_mint, transfer_checked_with_extensions},
+};
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct Refund<'info> {
     #[account(mut)]
@@ -29,44 +35,128 @@ pub struct Refund<'info> {
         associated_token::authority = escrow,
     )]
     vault: InterfaceAccount<'info, TokenAccount>,
+    associated_token_program: Program<'info, AssociatedToken>,
     token_program: Interface<'info, TokenInterface>,
     system_program: Program<'info, System>,
 }
 
 impl<'info> Refund<'info> {
-    pub fn refund_and_close_vault(&mut self) -> Result<()> {
+    pub fn ensure_time_constraint(&self) -> Result<()> {
+        self.escrow.ensure_can_refund(Clock::get()?.unix_timestamp)
+    }
+
+    pub fn vault_refund(&self) -> VaultRefund<'_, 'info> {
+        VaultRefund {
+            maker: self.maker.to_account_info(),
+            mint_a: &self.mint_a,
+            maker_ata_a: self.maker_ata_a.to_account_info(),
+            escrow: &self.escrow,
+            vault: &self.vault,
+            payer: self.maker.to_account_info(),
+            associated_token_program: self.associated_token_program.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+        }
+    }
+
+    // vault balance is not reloaded after the transfer, so it still holds the refunded amount
+    pub fn refunded_event(&self) -> EscrowRefunded {
+        EscrowRefunded {
+            seed: self.escrow.seed,
+            maker: self.escrow.maker,
+            mint_a: self.escrow.mint_a,
+            amount_a: self.vault.amount,
+        }
+    }
+}
+
+/// returns an escrow's deposit to its maker and closes the vaults, shared by refund and
+/// crank_refund
+pub struct VaultRefund<'a, 'info> {
+    pub maker: AccountInfo<'info>,
+    pub mint_a: &'a InterfaceAccount<'info, Mint>,
+    pub maker_ata_a: AccountInfo<'info>,
+    pub escrow: &'a Account<'info, Escrow>,
+    pub vault: &'a InterfaceAccount<'info, TokenAccount>,
+    // funds the maker's bundle token accounts when they do not exist yet
+    pub payer: AccountInfo<'info>,
+    pub associated_token_program: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+}
+
+impl<'info> VaultRefund<'_, 'info> {
+    pub fn refund_and_close_vault(&self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
         let signer_seeds: [&[&[u8]]; 1] = [&[
             b"escrow",
             self.maker.key.as_ref(),
             &self.escrow.seed.to_le_bytes()[..],
-            &[self.escrow.bump]
+            &[self.escrow.bump],
         ]];
-        
-        let cpi_program = self.token_program.to_account_info();
 
-        let cpi_accounts = TransferChecked {
-            from: self.vault.to_account_info(),
-            to: self.maker_ata_a.to_account_info(),
-            mint: self.mint_a.to_account_info(),
-            authority: self.escrow.to_account_info(),
-        };
+        // closing a wrapped SOL vault already hands its balance to the maker as lamports
+        if !is_native_mint(&self.mint_a.key()) {
+            let cpi_program = self.token_program.clone();
 
-        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+            let cpi_accounts = TransferChecked {
+                from: self.vault.to_account_info(),
+                to: self.maker_ata_a.clone(),
+                mint: self.mint_a.to_account_info(),
+                authority: self.escrow.to_account_info(),
+            };
+
+            let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds)
+                .with_remaining_accounts(remaining_accounts.to_vec());
 
-        transfer_checked(cpi_context, self.vault.amount, self.mint_a.decimals)?;
+            transfer_checked_with_extensions(cpi_context, self.vault.amount, self.mint_a.decimals)?;
+        }
 
-        let cpi_program = self.token_program.to_account_info();
+        let cpi_program = self.token_program.clone();
 
         let cpi_accounts = CloseAccount {
             account: self.vault.to_account_info(),
-            destination: self.maker.to_account_info(),
+            destination: self.maker.clone(),
             authority: self.escrow.to_account_info(),
         };
 
         let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
 
-        close_account(cpi_context)?;
-        
+        close_account(cpi_context)
+    }
+
+    // bundle accounts are `[mint, vault, maker ata]` per bundle_a leg
+    pub fn refund_bundle(
+        &self,
+        bundle_accounts: &[AccountInfo<'info>],
+        extra_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.maker.key.as_ref(),
+            &self.escrow.seed.to_le_bytes()[..],
+            &[self.escrow.bump],
+        ]];
+
+        let bundle = BundleContext {
+            token_program: self.token_program.clone(),
+            associated_token_program: self.associated_token_program.clone(),
+            system_program: self.system_program.clone(),
+            payer: self.payer.clone(),
+            signer_seeds: &signer_seeds,
+            extra_accounts,
+        };
+
+        let escrow = self.escrow.to_account_info();
+        for (leg, accounts) in self
+            .escrow
+            .bundle_a
+            .iter()
+            .zip(bundle_accounts.chunks_exact(BUNDLE_LEG_ACCOUNTS))
+        {
+            bundle.move_leg(leg, accounts, &escrow, &self.maker, None)?;
+            bundle.close_vault(accounts, 1, &escrow, &self.maker)?;
+        }
+
         Ok(())
     }
-}
\ No newline at end of file
+}
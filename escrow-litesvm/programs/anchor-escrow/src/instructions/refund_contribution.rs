@@ -0,0 +1,124 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    close_account, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+
+use crate::{
+    events::EscrowContributionRefunded,
+    state::{Escrow, Receipt},
+    utils::transfer_checked_with_extensions,
+    EscrowError,
+};
+
+//Contributor pulls its mint b back out of a crowd escrow that expired unfilled
+//the last one out closes vault_b, after which the maker can refund the vault
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RefundContribution<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    pub maker: SystemAccount<'info>,
+    pub mint_b: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = taker,
+    )]
+    pub taker_ata_b: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        has_one = maker,
+        has_one = mint_b,
+        constraint = escrow.crowd @ EscrowError::NotCrowdEscrow,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = escrow,
+    )]
+    pub vault_b: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        close = taker,
+        has_one = escrow,
+        has_one = taker,
+        seeds = [b"receipt", escrow.key().as_ref(), taker.key().as_ref()],
+        bump = receipt.bump,
+    )]
+    pub receipt: Account<'info, Receipt>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> RefundContribution<'info> {
+    pub fn ensure_refundable(&self) -> Result<()> {
+        self.escrow
+            .ensure_can_refund_contribution(Clock::get()?.unix_timestamp)
+    }
+
+    // refunds are pro rata to what vault_b holds, so a mint b transfer fee is shared by everyone
+    pub fn refund_contribution(
+        &mut self,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<u64> {
+        let refund = Escrow::crowd_share(
+            self.vault_b.amount,
+            self.receipt.amount,
+            self.escrow.contributed,
+        )?;
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.maker.key.as_ref(),
+            &self.escrow.seed.to_le_bytes()[..],
+            &[self.escrow.bump],
+        ]];
+
+        if refund > 0 {
+            let cpi_program = self.token_program.to_account_info();
+
+            let cpi_accounts = TransferChecked {
+                from: self.vault_b.to_account_info(),
+                to: self.taker_ata_b.to_account_info(),
+                authority: self.escrow.to_account_info(),
+                mint: self.mint_b.to_account_info(),
+            };
+
+            let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds)
+                .with_remaining_accounts(remaining_accounts.to_vec());
+
+            transfer_checked_with_extensions(cpi_context, refund, self.mint_b.decimals)?;
+        }
+
+        self.escrow.contributed -= self.receipt.amount;
+
+        if self.escrow.contributed > 0 {
+            return Ok(refund);
+        }
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = CloseAccount {
+            account: self.vault_b.to_account_info(),
+            destination: self.taker.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        close_account(cpi_context)?;
+
+        Ok(refund)
+    }
+
+    pub fn contribution_refunded_event(&self, amount_b: u64) -> EscrowContributionRefunded {
+        EscrowContributionRefunded {
+            seed: self.escrow.seed,
+            maker: self.escrow.maker,
+            taker: self.taker.key(),
+            amount_b,
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+    },
+};
+
+use crate::{
+    events::EscrowReleased,
+    state::{Config, Escrow, MAX_SPLIT_BPS},
+    utils::transfer_checked_with_extensions,
+    EscrowError,
+};
+
+//Settle a funded arbitrated escrow
+//taker_bps of the vault goes to the taker and taker_bps of vault_b to the maker,
+//the remainder of each vault returns to whoever deposited it
+//10000 completes the swap, 0 unwinds it
+//the protocol fee is taken on the mint b the maker receives, same as take
+#[event_cpi]
+#[derive(Accounts)]
+pub struct Release<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+    #[account(mut)]
+    pub taker: SystemAccount<'info>,
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+    )]
+    pub maker_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = mint_b,
+        associated_token::authority = maker,
+    )]
+    pub maker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = mint_a,
+        associated_token::authority = taker,
+    )]
+    pub taker_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = mint_b,
+        associated_token::authority = taker,
+    )]
+    pub taker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        has_one = mint_a,
+        has_one = mint_b,
+        constraint = escrow.taker == Some(taker.key()) @ EscrowError::EscrowNotFunded,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Box<Account<'info, Escrow>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+    )]
+    pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = escrow,
+    )]
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = fee_recipient,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    pub fee_recipient: SystemAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = mint_b,
+        associated_token::authority = fee_recipient,
+    )]
+    pub treasury_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Release<'info> {
+    pub fn ensure_can_release(&self, taker_bps: u16) -> Result<()> {
+        require!(taker_bps <= MAX_SPLIT_BPS, EscrowError::InvalidSplit);
+        require!(
+            self.escrow.can_release(self.authority.key, taker_bps),
+            EscrowError::UnauthorizedArbitration
+        );
+        Ok(())
+    }
+
+    /// `taker_bps` share of `amount`, rounded down
+    pub fn split(amount: u64, taker_bps: u16) -> Result<u64> {
+        let share = (amount as u128)
+            .checked_mul(taker_bps as u128)
+            .and_then(|v| v.checked_div(MAX_SPLIT_BPS as u128))
+            .ok_or(EscrowError::MathOverflow)?;
+
+        u64::try_from(share).map_err(|_| EscrowError::MathOverflow.into())
+    }
+
+    pub fn release_and_close_vaults(
+        &mut self,
+        taker_bps: u16,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<(u64, u64)> {
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.maker.key.as_ref(),
+            &self.escrow.seed.to_le_bytes()[..],
+            &[self.escrow.bump],
+        ]];
+
+        let to_taker_a = Self::split(self.vault.amount, taker_bps)?;
+        let to_maker_b = Self::split(self.vault_b.amount, taker_bps)?;
+        let fee = self.config.fee_for(to_maker_b)?;
+
+        let legs = [
+            (&self.vault, &self.mint_a, &self.taker_ata_a, to_taker_a),
+            (
+                &self.vault,
+                &self.mint_a,
+                &self.maker_ata_a,
+                self.vault.amount - to_taker_a,
+            ),
+            (
+                &self.vault_b,
+                &self.mint_b,
+                &self.maker_ata_b,
+                to_maker_b - fee,
+            ),
+            (&self.vault_b, &self.mint_b, &self.treasury_ata_b, fee),
+            (
+                &self.vault_b,
+                &self.mint_b,
+                &self.taker_ata_b,
+                self.vault_b.amount - to_maker_b,
+            ),
+        ];
+
+        for (from, mint, to, amount) in legs {
+            if amount == 0 {
+                continue;
+            }
+
+            let cpi_accounts = TransferChecked {
+                from: from.to_account_info(),
+                to: to.to_account_info(),
+                authority: self.escrow.to_account_info(),
+                mint: mint.to_account_info(),
+            };
+
+            let cpi_context = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                cpi_accounts,
+                &signer_seeds,
+            )
+            .with_remaining_accounts(remaining_accounts.to_vec());
+
+            transfer_checked_with_extensions(cpi_context, amount, mint.decimals)?;
+        }
+
+        // each vault's rent goes back to whoever paid for it
+        for (vault, destination) in [(&self.vault, &self.maker), (&self.vault_b, &self.taker)] {
+            let cpi_accounts = CloseAccount {
+                account: vault.to_account_info(),
+                destination: destination.to_account_info(),
+                authority: self.escrow.to_account_info(),
+            };
+
+            let cpi_context = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                cpi_accounts,
+                &signer_seeds,
+            );
+
+            close_account(cpi_context)?;
+        }
+
+        Ok((to_taker_a, to_maker_b))
+    }
+
+    pub fn released_event(&self, taker_bps: u16, amount_a: u64, amount_b: u64) -> EscrowReleased {
+        EscrowReleased {
+            seed: self.escrow.seed,
+            maker: self.escrow.maker,
+            taker: self.taker.key(),
+            released_by: self.authority.key(),
+            taker_bps,
+            amount_a,
+            amount_b,
+        }
+    }
+}
@@ -1,9 +1,19 @@
 use anchor_lang::prelude::*;
-use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked, CloseAccount, close_account}};
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, CloseAccount, close_account}};
 
-use crate::state::Escrow;
+use crate::{
+    bundle::{BundleContext, BUNDLE_B_LEG_ACCOUNTS, BUNDLE_LEG_ACCOUNTS},
+    events::EscrowTaken,
+    state::{Config, Escrow},
+    utils::{
+        init_ata_if_needed, is_native_mint, net_of_fee, transfer_checked_with_extensions,
+        unwrap_sol, MintBPayment,
+    },
+    EscrowError,
+};
 
 //Create context
+#[event_cpi]
 #[derive(Accounts)]
 pub struct Take<'info> {
     #[account(mut)]
@@ -12,32 +22,27 @@ pub struct Take<'info> {
     pub maker: SystemAccount<'info>,
     pub mint_a: InterfaceAccount<'info, Mint>,
     pub mint_b: InterfaceAccount<'info, Mint>,
-    #[account(
-        init_if_needed,
-        payer = taker,
-        associated_token::mint = mint_a,
-        associated_token::authority = taker,
-    )]
-    pub taker_ata_a: InterfaceAccount<'info, TokenAccount>,
-    #[account(
-        mut,
-        associated_token::mint = mint_b,
-        associated_token::authority = taker,
-    )]
-    pub taker_ata_b: InterfaceAccount<'info, TokenAccount>,
-    #[account(
-        init_if_needed,
-        payer = taker,
-        associated_token::mint = mint_b,
-        associated_token::authority = maker,
-    )]
-    pub maker_ata_b: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: taker's mint a associated token account, created in `withdraw_and_close_vault`
+    /// unless it already exists
+    #[account(mut)]
+    pub taker_ata_a: UncheckedAccount<'info>,
+    /// CHECK: taker's mint b account, checked by the token program on transfer, unused when
+    /// mint b is native SOL
+    #[account(mut)]
+    pub taker_ata_b: UncheckedAccount<'info>,
+    /// CHECK: maker's mint b associated token account, created on transfer unless mint b is
+    /// native SOL
+    #[account(mut)]
+    pub maker_ata_b: UncheckedAccount<'info>,
     #[account(
         mut,
         close = maker,
         has_one = maker,
         has_one = mint_a,
         has_one = mint_b,
+        constraint = escrow.can_be_taken_by(taker.key) @ EscrowError::UnauthorizedTaker,
+        constraint = escrow.arbiter.is_none() @ EscrowError::ArbitratedEscrow,
+        constraint = !escrow.crowd @ EscrowError::CrowdEscrow,
         seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
         bump = escrow.bump,
     )]
@@ -48,31 +53,88 @@ pub struct Take<'info> {
         associated_token::authority = escrow,
     )]
     pub vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = fee_recipient,
+        constraint = !config.paused @ EscrowError::ProgramPaused,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(mut)]
+    pub fee_recipient: SystemAccount<'info>,
+    /// CHECK: fee recipient's mint b associated token account, created when a fee is paid in a
+    /// mint other than native SOL
+    #[account(mut)]
+    pub treasury_ata_b: UncheckedAccount<'info>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
-//Deposit tokens from taker to maker
+//Deposit tokens from taker to maker, less the protocol fee sent to treasury
 //Transfer tokens from vault to taker
 //Close vault account
 impl<'info> Take<'info> {
-    pub fn deposit(&mut self) -> Result<()> {
-        let cpi_program = self.token_program.to_account_info();
+    pub fn ensure_time_threshold(&self) -> Result<()> {
+        self.escrow.ensure_can_take(Clock::get()?.unix_timestamp)
+    }
 
-        let cpi_accounts = TransferChecked {
-            from: self.taker_ata_b.to_account_info(),
-            to: self.maker_ata_b.to_account_info(),
-            authority: self.taker.to_account_info(),
-            mint: self.mint_b.to_account_info(),
-        };
+    // auction offers settle at the current price, the escrow is closed right after
+    pub fn apply_auction_price(&mut self) -> Result<()> {
+        self.escrow.receive = self
+            .escrow
+            .current_receive(Clock::get()?.unix_timestamp)?;
+        Ok(())
+    }
+
+    pub fn ensure_preimage(&self, preimage: Option<&[u8]>) -> Result<()> {
+        self.escrow.ensure_preimage(preimage)
+    }
 
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+    pub fn ensure_revision(&self, expected_revision: Option<u64>) -> Result<()> {
+        self.escrow.ensure_revision(expected_revision)
+    }
 
-        transfer_checked(cpi_ctx, self.escrow.receive, self.mint_b.decimals)
+    // taker pays at most `expected_receive` and gets at least `min_amount_a` out of the vault,
+    // after the mint a transfer fee
+    pub fn ensure_slippage(&self, expected_receive: u64, min_amount_a: u64) -> Result<()> {
+        let amount_a = net_of_fee(&self.mint_a.to_account_info(), self.vault.amount)?;
+        require!(
+            self.escrow.receive <= expected_receive && amount_a >= min_amount_a,
+            EscrowError::SlippageExceeded
+        );
+        Ok(())
     }
 
-    pub fn withdraw_and_close_vault(&mut self) -> Result<()> {
+    pub fn deposit(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let fee = self.config.fee_for(self.escrow.receive)?;
+
+        let payment = MintBPayment {
+            taker: self.taker.to_account_info(),
+            taker_ata_b: self.taker_ata_b.to_account_info(),
+            maker: self.maker.to_account_info(),
+            maker_ata_b: self.maker_ata_b.to_account_info(),
+            fee_recipient: self.fee_recipient.to_account_info(),
+            treasury_ata_b: self.treasury_ata_b.to_account_info(),
+            mint_b: self.mint_b.to_account_info(),
+            decimals: self.mint_b.decimals,
+            associated_token_program: self.associated_token_program.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+        };
+
+        payment.pay(
+            self.escrow.receive,
+            fee,
+            self.escrow.receive_is_net,
+            remaining_accounts,
+        )
+    }
+
+    pub fn withdraw_and_close_vault(
+        &mut self,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
         let signer_seeds: [&[&[u8]]; 1] = [&[
             b"escrow",
             self.maker.key.as_ref(),
@@ -80,6 +142,16 @@ impl<'info> Take<'info> {
             &[self.escrow.bump]
         ]];
 
+        let opened_ata_a = init_ata_if_needed(
+            self.taker_ata_a.to_account_info(),
+            self.taker.to_account_info(),
+            self.mint_a.to_account_info(),
+            self.taker.to_account_info(),
+            self.associated_token_program.to_account_info(),
+            self.token_program.to_account_info(),
+            self.system_program.to_account_info(),
+        )?;
+
         let cpi_program = self.token_program.to_account_info();
 
         let cpi_accounts = TransferChecked {
@@ -89,9 +161,20 @@ impl<'info> Take<'info> {
             mint: self.mint_a.to_account_info(),
         };
 
-        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+
+        transfer_checked_with_extensions(cpi_context, self.vault.amount, self.mint_a.decimals)?;
 
-        transfer_checked(cpi_context, self.vault.amount, self.mint_a.decimals)?;
+        // SOL offers land in the taker's wallet as lamports when the wrapped SOL account was
+        // opened for this take, wrapped SOL the taker already held stays wrapped with the release
+        if is_native_mint(&self.mint_a.key()) && opened_ata_a {
+            unwrap_sol(
+                self.token_program.to_account_info(),
+                self.taker.to_account_info(),
+                self.taker_ata_a.to_account_info(),
+            )?;
+        }
 
         let cpi_program = self.token_program.to_account_info();
 
@@ -105,4 +188,82 @@ impl<'info> Take<'info> {
 
         close_account(cpi_context)
     }
-}
\ No newline at end of file
+
+    // bundle accounts are `[mint, vault, taker ata]` per bundle_a leg followed by
+    // `[mint, taker ata, maker ata, treasury ata]` per bundle_b leg, the protocol fee is taken
+    // on every bundle_b leg the same as on mint b
+    pub fn settle_bundle(
+        &mut self,
+        bundle_accounts: &[AccountInfo<'info>],
+        extra_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.maker.key.as_ref(),
+            &self.escrow.seed.to_le_bytes()[..],
+            &[self.escrow.bump],
+        ]];
+
+        let bundle = BundleContext {
+            token_program: self.token_program.to_account_info(),
+            associated_token_program: self.associated_token_program.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+            payer: self.taker.to_account_info(),
+            signer_seeds: &signer_seeds,
+            extra_accounts,
+        };
+
+        let maker = self.maker.to_account_info();
+        let taker = self.taker.to_account_info();
+        let escrow = self.escrow.to_account_info();
+        let (accounts_a, accounts_b) =
+            bundle_accounts.split_at(self.escrow.bundle_a.len() * BUNDLE_LEG_ACCOUNTS);
+
+        for (leg, accounts) in self
+            .escrow
+            .bundle_a
+            .iter()
+            .zip(accounts_a.chunks_exact(BUNDLE_LEG_ACCOUNTS))
+        {
+            bundle.move_leg(leg, accounts, &escrow, &taker, None)?;
+            bundle.close_vault(accounts, 1, &escrow, &maker)?;
+        }
+
+        let fee_recipient = self.fee_recipient.to_account_info();
+        for (leg, accounts) in self
+            .escrow
+            .bundle_b
+            .iter()
+            .zip(accounts_b.chunks_exact(BUNDLE_B_LEG_ACCOUNTS))
+        {
+            let [mint, from, to, treasury] = accounts else {
+                return err!(EscrowError::InvalidBundleAccounts);
+            };
+            let fee = self.config.fee_for(leg.amount)?;
+
+            let to_maker = [mint.clone(), from.clone(), to.clone()];
+            bundle.move_leg(leg, &to_maker, &taker, &maker, Some(leg.amount - fee))?;
+
+            if fee > 0 {
+                let to_treasury = [mint.clone(), from.clone(), treasury.clone()];
+                bundle.move_leg(leg, &to_treasury, &taker, &fee_recipient, Some(fee))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // vault balance is not reloaded after the transfer, so it still holds the released amount
+    pub fn taken_event(&self) -> EscrowTaken {
+        EscrowTaken {
+            seed: self.escrow.seed,
+            maker: self.escrow.maker,
+            taker: self.taker.key(),
+            mint_a: self.escrow.mint_a,
+            mint_b: self.escrow.mint_b,
+            amount_a: self.vault.amount,
+            amount_b: self.escrow.receive,
+            remaining_receive: 0,
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+    },
+};
+
+use crate::{
+    events::EscrowTaken,
+    state::{Config, Escrow},
+    utils::{
+        init_ata_if_needed, is_native_mint, net_of_fee, transfer_checked_with_extensions,
+        unwrap_sol, MintBPayment,
+    },
+    EscrowError,
+};
+
+//Create context
+//escrow is not closed by constraint, it is closed manually once the offer is fully filled
+#[event_cpi]
+#[derive(Accounts)]
+pub struct TakePartial<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    pub mint_b: InterfaceAccount<'info, Mint>,
+    /// CHECK: taker's mint a associated token account, created in `withdraw_and_close_if_filled`
+    /// unless it already exists
+    #[account(mut)]
+    pub taker_ata_a: UncheckedAccount<'info>,
+    /// CHECK: taker's mint b account, checked by the token program on transfer, unused when
+    /// mint b is native SOL
+    #[account(mut)]
+    pub taker_ata_b: UncheckedAccount<'info>,
+    /// CHECK: maker's mint b associated token account, created on transfer unless mint b is
+    /// native SOL
+    #[account(mut)]
+    pub maker_ata_b: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        has_one = maker,
+        has_one = mint_a,
+        has_one = mint_b,
+        constraint = escrow.can_be_taken_by(taker.key) @ EscrowError::UnauthorizedTaker,
+        constraint = escrow.arbiter.is_none() @ EscrowError::ArbitratedEscrow,
+        constraint = !escrow.crowd @ EscrowError::CrowdEscrow,
+        constraint = escrow.is_partially_fillable() @ EscrowError::NotPartiallyFillable,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = fee_recipient,
+        constraint = !config.paused @ EscrowError::ProgramPaused,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(mut)]
+    pub fee_recipient: SystemAccount<'info>,
+    /// CHECK: fee recipient's mint b associated token account, created when a fee is paid in a
+    /// mint other than native SOL
+    #[account(mut)]
+    pub treasury_ata_b: UncheckedAccount<'info>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+//Deposit part of the asked tokens from taker to maker, less the protocol fee sent to treasury
+//Release the proportional share of the vault to taker
+//Close vault and escrow once the offer is fully filled
+impl<'info> TakePartial<'info> {
+    pub fn ensure_time_threshold(&self) -> Result<()> {
+        self.escrow.ensure_can_take(Clock::get()?.unix_timestamp)
+    }
+
+    pub fn ensure_revision(&self, expected_revision: Option<u64>) -> Result<()> {
+        self.escrow.ensure_revision(expected_revision)
+    }
+
+    // remaining ask is at most `expected_receive` and `amount` releases at least `min_amount_a`,
+    // after the mint a transfer fee
+    pub fn ensure_slippage(
+        &self,
+        amount: u64,
+        expected_receive: u64,
+        min_amount_a: u64,
+    ) -> Result<()> {
+        let amount_a = net_of_fee(
+            &self.mint_a.to_account_info(),
+            self.amount_to_release(amount)?,
+        )?;
+        require!(
+            self.escrow.receive <= expected_receive && amount_a >= min_amount_a,
+            EscrowError::SlippageExceeded
+        );
+        Ok(())
+    }
+
+    pub fn deposit(
+        &mut self,
+        amount: u64,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        require!(
+            amount > 0 && amount <= self.escrow.receive,
+            EscrowError::InvalidTakeAmount
+        );
+
+        let fee = self.config.fee_for(amount)?;
+
+        let payment = MintBPayment {
+            taker: self.taker.to_account_info(),
+            taker_ata_b: self.taker_ata_b.to_account_info(),
+            maker: self.maker.to_account_info(),
+            maker_ata_b: self.maker_ata_b.to_account_info(),
+            fee_recipient: self.fee_recipient.to_account_info(),
+            treasury_ata_b: self.treasury_ata_b.to_account_info(),
+            mint_b: self.mint_b.to_account_info(),
+            decimals: self.mint_b.decimals,
+            associated_token_program: self.associated_token_program.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+        };
+
+        payment.pay(amount, fee, self.escrow.receive_is_net, remaining_accounts)
+    }
+
+    /// share of the vault released for `amount` of mint b, rounded down in favour of the maker
+    pub fn amount_to_release(&self, amount: u64) -> Result<u64> {
+        if amount == self.escrow.receive {
+            return Ok(self.vault.amount);
+        }
+
+        let share = (self.vault.amount as u128)
+            .checked_mul(amount as u128)
+            .and_then(|v| v.checked_div(self.escrow.receive as u128))
+            .ok_or(EscrowError::MathOverflow)?;
+
+        u64::try_from(share).map_err(|_| EscrowError::MathOverflow.into())
+    }
+
+    pub fn withdraw_and_close_if_filled(
+        &mut self,
+        amount: u64,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<u64> {
+        let release = self.amount_to_release(amount)?;
+        require!(release > 0, EscrowError::InvalidTakeAmount);
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.maker.key.as_ref(),
+            &self.escrow.seed.to_le_bytes()[..],
+            &[self.escrow.bump],
+        ]];
+
+        let opened_ata_a = init_ata_if_needed(
+            self.taker_ata_a.to_account_info(),
+            self.taker.to_account_info(),
+            self.mint_a.to_account_info(),
+            self.taker.to_account_info(),
+            self.associated_token_program.to_account_info(),
+            self.token_program.to_account_info(),
+            self.system_program.to_account_info(),
+        )?;
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            to: self.taker_ata_a.to_account_info(),
+            authority: self.escrow.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+
+        transfer_checked_with_extensions(cpi_context, release, self.mint_a.decimals)?;
+
+        // SOL offers land in the taker's wallet as lamports when the wrapped SOL account was
+        // opened for this take, wrapped SOL the taker already held stays wrapped with the release
+        if is_native_mint(&self.mint_a.key()) && opened_ata_a {
+            unwrap_sol(
+                self.token_program.to_account_info(),
+                self.taker.to_account_info(),
+                self.taker_ata_a.to_account_info(),
+            )?;
+        }
+
+        self.escrow.receive = self
+            .escrow
+            .receive
+            .checked_sub(amount)
+            .ok_or(EscrowError::MathOverflow)?;
+        self.escrow.deposited = self.escrow.deposited.saturating_sub(release);
+
+        if self.escrow.receive > 0 {
+            return Ok(release);
+        }
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = CloseAccount {
+            account: self.vault.to_account_info(),
+            destination: self.maker.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        close_account(cpi_context)?;
+
+        self.escrow.close(self.maker.to_account_info())?;
+
+        Ok(release)
+    }
+
+    pub fn taken_event(&self, amount_a: u64, amount_b: u64) -> EscrowTaken {
+        EscrowTaken {
+            seed: self.escrow.seed,
+            maker: self.escrow.maker,
+            taker: self.taker.key(),
+            mint_a: self.escrow.mint_a,
+            mint_b: self.escrow.mint_b,
+            amount_a,
+            amount_b,
+            remaining_receive: self.escrow.receive,
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    events::ConfigUpdated,
+    state::{Config, MAX_FEE_BPS},
+    EscrowError,
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        has_one = admin,
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> UpdateConfig<'info> {
+    pub fn update_config(
+        &mut self,
+        admin: Option<Pubkey>,
+        fee_bps: Option<u16>,
+        fee_recipient: Option<Pubkey>,
+        paused: Option<bool>,
+    ) -> Result<()> {
+        if let Some(admin) = admin {
+            self.config.admin = admin;
+        }
+
+        if let Some(fee_bps) = fee_bps {
+            require!(fee_bps <= MAX_FEE_BPS, EscrowError::InvalidFee);
+            self.config.fee_bps = fee_bps;
+        }
+
+        if let Some(fee_recipient) = fee_recipient {
+            self.config.fee_recipient = fee_recipient;
+        }
+
+        if let Some(paused) = paused {
+            self.config.paused = paused;
+        }
+
+        Ok(())
+    }
+
+    pub fn config_updated_event(&self) -> ConfigUpdated {
+        ConfigUpdated {
+            admin: self.config.admin,
+            fee_bps: self.config.fee_bps,
+            fee_recipient: self.config.fee_recipient,
+            paused: self.config.paused,
+        }
+    }
+}
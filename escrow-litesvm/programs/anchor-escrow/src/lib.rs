@@ -3,11 +3,17 @@
 
 use anchor_lang::prelude::*;
 
-mod instructions;
-mod state;
+mod bundle;
+mod events;
+pub mod instructions;
+mod signature;
+pub mod state;
 mod tests;
+mod utils;
 
+use bundle::split_bundle_accounts;
 use instructions::*;
+use state::Order;
 
 declare_id!("FircrADQ2wgGuvpm8qneNCfKM7o5zoHTWnDQxngpTQ3J");
 
@@ -15,28 +21,219 @@ declare_id!("FircrADQ2wgGuvpm8qneNCfKM7o5zoHTWnDQxngpTQ3J");
 pub mod anchor_escrow {
     use super::*;
 
-    pub fn make(ctx: Context<Make>, seed: u64, deposit: u64, receive: u64) -> Result<()> {
-        ctx.accounts.init_escrow(seed, receive, &ctx.bumps)?;
-        ctx.accounts.deposit(deposit)
+    pub fn init_config(
+        ctx: Context<InitConfig>,
+        fee_bps: u16,
+        fee_recipient: Pubkey,
+    ) -> Result<()> {
+        ctx.accounts
+            .init_config(fee_bps, fee_recipient, &ctx.bumps)?;
+        emit_cpi!(ctx.accounts.config_updated_event());
+        Ok(())
     }
 
-    pub fn refund(ctx: Context<Refund>) -> Result<()> {
-        ctx.accounts.refund_and_close_vault()
+    pub fn update_config(
+        ctx: Context<UpdateConfig>,
+        admin: Option<Pubkey>,
+        fee_bps: Option<u16>,
+        fee_recipient: Option<Pubkey>,
+        paused: Option<bool>,
+    ) -> Result<()> {
+        ctx.accounts
+            .update_config(admin, fee_bps, fee_recipient, paused)?;
+        emit_cpi!(ctx.accounts.config_updated_event());
+        Ok(())
     }
 
-    pub fn take(ctx: Context<Take>) -> Result<()> {
-        ctx.accounts.deposit()?;
-        ctx.accounts.withdraw_and_close_vault()
+    pub fn make<'info>(
+        ctx: Context<'_, '_, '_, 'info, Make<'info>>,
+        seed: u64,
+        deposit: u64,
+        receive: u64,
+        args: MakeArgs,
+    ) -> Result<()> {
+        let (bundle_accounts, extra_accounts) =
+            split_bundle_accounts(ctx.remaining_accounts, args.bundle_a.len(), 0)?;
+        ctx.accounts.init_escrow(seed, receive, args, &ctx.bumps)?;
+        ctx.accounts.deposit(deposit, extra_accounts)?;
+        ctx.accounts
+            .deposit_bundle(bundle_accounts, extra_accounts)?;
+        ctx.accounts.fund_crank_tip()?;
+        emit_cpi!(ctx.accounts.made_event());
+        Ok(())
     }
 
-    pub fn take_after_time(ctx: Context<TakeAfterTime>) -> Result<()> {
+    pub fn refund<'info>(ctx: Context<'_, '_, '_, 'info, Refund<'info>>) -> Result<()> {
+        ctx.accounts.ensure_time_constraint()?;
+        let (bundle_accounts, extra_accounts) = split_bundle_accounts(
+            ctx.remaining_accounts,
+            ctx.accounts.escrow.bundle_a.len(),
+            0,
+        )?;
+        let vault_refund = ctx.accounts.vault_refund();
+        vault_refund.refund_and_close_vault(extra_accounts)?;
+        vault_refund.refund_bundle(bundle_accounts, extra_accounts)?;
+        emit_cpi!(ctx.accounts.refunded_event());
+        Ok(())
+    }
+
+    pub fn crank_refund<'info>(ctx: Context<'_, '_, '_, 'info, CrankRefund<'info>>) -> Result<()> {
+        ctx.accounts.ensure_expired()?;
+        let (bundle_accounts, extra_accounts) = split_bundle_accounts(
+            ctx.remaining_accounts,
+            ctx.accounts.escrow.bundle_a.len(),
+            0,
+        )?;
+        let vault_refund = ctx.accounts.vault_refund();
+        vault_refund.refund_and_close_vault(extra_accounts)?;
+        vault_refund.refund_bundle(bundle_accounts, extra_accounts)?;
+        ctx.accounts.pay_tip()?;
+        emit_cpi!(ctx.accounts.crank_refunded_event());
+        Ok(())
+    }
+
+    pub fn take<'info>(
+        ctx: Context<'_, '_, '_, 'info, Take<'info>>,
+        expected_revision: Option<u64>,
+        expected_receive: u64,
+        min_amount_a: u64,
+        preimage: Option<Vec<u8>>,
+    ) -> Result<()> {
         ctx.accounts.ensure_time_threshold()?;
-        ctx.accounts.deposit()?;
-        ctx.accounts.withdraw_and_close_vault()
+        ctx.accounts.ensure_preimage(preimage.as_deref())?;
+        ctx.accounts.ensure_revision(expected_revision)?;
+        ctx.accounts.apply_auction_price()?;
+        ctx.accounts
+            .ensure_slippage(expected_receive, min_amount_a)?;
+        let (bundle_accounts, extra_accounts) = split_bundle_accounts(
+            ctx.remaining_accounts,
+            ctx.accounts.escrow.bundle_a.len(),
+            ctx.accounts.escrow.bundle_b.len(),
+        )?;
+        ctx.accounts.deposit(extra_accounts)?;
+        ctx.accounts.withdraw_and_close_vault(extra_accounts)?;
+        ctx.accounts
+            .settle_bundle(bundle_accounts, extra_accounts)?;
+        emit_cpi!(ctx.accounts.taken_event());
+        Ok(())
     }
 
-    pub fn refund_before_time(ctx: Context<RefundBeforeTime>) -> Result<()> {
-        ctx.accounts.ensure_time_constraint()?;
-        ctx.accounts.refund_and_close_vault()
+    pub fn take_partial<'info>(
+        ctx: Context<'_, '_, '_, 'info, TakePartial<'info>>,
+        amount: u64,
+        expected_revision: Option<u64>,
+        expected_receive: u64,
+        min_amount_a: u64,
+    ) -> Result<()> {
+        ctx.accounts.ensure_time_threshold()?;
+        ctx.accounts.ensure_revision(expected_revision)?;
+        ctx.accounts
+            .ensure_slippage(amount, expected_receive, min_amount_a)?;
+        ctx.accounts.deposit(amount, ctx.remaining_accounts)?;
+        let released = ctx
+            .accounts
+            .withdraw_and_close_if_filled(amount, ctx.remaining_accounts)?;
+        emit_cpi!(ctx.accounts.taken_event(released, amount));
+        Ok(())
+    }
+
+    pub fn fund_arbitrated<'info>(
+        ctx: Context<'_, '_, '_, 'info, FundArbitrated<'info>>,
+        expected_revision: Option<u64>,
+        expected_receive: u64,
+        min_amount_a: u64,
+    ) -> Result<()> {
+        ctx.accounts.ensure_can_fund()?;
+        ctx.accounts.ensure_revision(expected_revision)?;
+        ctx.accounts
+            .ensure_slippage(expected_receive, min_amount_a)?;
+        ctx.accounts.deposit(ctx.remaining_accounts)?;
+        emit_cpi!(ctx.accounts.funded_event());
+        Ok(())
+    }
+
+    pub fn raise_dispute(ctx: Context<RaiseDispute>) -> Result<()> {
+        ctx.accounts.raise_dispute()?;
+        emit_cpi!(ctx.accounts.disputed_event());
+        Ok(())
+    }
+
+    pub fn release<'info>(
+        ctx: Context<'_, '_, '_, 'info, Release<'info>>,
+        taker_bps: u16,
+    ) -> Result<()> {
+        ctx.accounts.ensure_can_release(taker_bps)?;
+        let (amount_a, amount_b) = ctx
+            .accounts
+            .release_and_close_vaults(taker_bps, ctx.remaining_accounts)?;
+        emit_cpi!(ctx.accounts.released_event(taker_bps, amount_a, amount_b));
+        Ok(())
+    }
+
+    pub fn amend<'info>(
+        ctx: Context<'_, '_, '_, 'info, Amend<'info>>,
+        top_up: u64,
+        withdraw: u64,
+        receive: u64,
+    ) -> Result<()> {
+        ctx.accounts.top_up(top_up, ctx.remaining_accounts)?;
+        ctx.accounts.withdraw(withdraw, ctx.remaining_accounts)?;
+        ctx.accounts.update_terms(receive)?;
+        emit_cpi!(ctx.accounts.amended_event());
+        Ok(())
+    }
+
+    pub fn contribute<'info>(
+        ctx: Context<'_, '_, '_, 'info, Contribute<'info>>,
+        amount: u64,
+        expected_revision: Option<u64>,
+    ) -> Result<()> {
+        ctx.accounts.ensure_can_contribute(amount)?;
+        ctx.accounts.ensure_revision(expected_revision)?;
+        ctx.accounts
+            .contribute(amount, ctx.remaining_accounts, &ctx.bumps)?;
+        ctx.accounts.pay_maker_if_filled(ctx.remaining_accounts)?;
+        emit_cpi!(ctx.accounts.contributed_event(amount));
+        Ok(())
+    }
+
+    pub fn claim<'info>(ctx: Context<'_, '_, '_, 'info, Claim<'info>>) -> Result<()> {
+        let amount_a = ctx
+            .accounts
+            .claim_and_close_if_done(ctx.remaining_accounts)?;
+        emit_cpi!(ctx.accounts.claimed_event(amount_a));
+        Ok(())
+    }
+
+    pub fn refund_contribution<'info>(
+        ctx: Context<'_, '_, '_, 'info, RefundContribution<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.ensure_refundable()?;
+        let amount_b = ctx.accounts.refund_contribution(ctx.remaining_accounts)?;
+        emit_cpi!(ctx.accounts.contribution_refunded_event(amount_b));
+        Ok(())
+    }
+
+    pub fn fill_order<'info>(
+        ctx: Context<'_, '_, '_, 'info, FillOrder<'info>>,
+        order: Order,
+    ) -> Result<()> {
+        ctx.accounts.ensure_can_fill(&order)?;
+        ctx.accounts
+            .fill(&order, ctx.remaining_accounts, &ctx.bumps)?;
+        emit_cpi!(ctx.accounts.filled_event(&order));
+        Ok(())
+    }
+
+    pub fn cancel_order(ctx: Context<CancelOrder>, nonce: u64) -> Result<()> {
+        ctx.accounts.cancel_order(nonce, &ctx.bumps)?;
+        emit_cpi!(ctx.accounts.cancelled_event());
+        Ok(())
+    }
+
+    pub fn migrate_escrow(ctx: Context<MigrateEscrow>) -> Result<()> {
+        let from_version = ctx.accounts.migrate()?;
+        emit_cpi!(ctx.accounts.migrated_event(from_version)?);
+        Ok(())
     }
 }
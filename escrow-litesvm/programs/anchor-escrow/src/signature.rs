@@ -0,0 +1,74 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{ed25519_program, sysvar::instructions::get_instruction_relative},
+};
+
+use crate::EscrowError;
+
+// layout of the Ed25519 program instruction data: a u8 signature count and a padding byte,
+// then one 14 byte offsets struct per signature
+const OFFSETS_START: usize = 2;
+const OFFSETS_LEN: usize = 14;
+const PUBKEY_LEN: usize = 32;
+const SIGNATURE_LEN: usize = 64;
+// instruction index the Ed25519 program reads as "this instruction"
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// checks that the instruction right before the current one had the Ed25519 program verify
+/// `signer`'s signature over exactly `message`
+///
+/// the precompile fails the whole transaction on a bad signature, so all that is left to check
+/// here is that it was asked to verify the key and message we expect
+pub fn verify_ed25519_signature(
+    instructions: &AccountInfo,
+    signer: &Pubkey,
+    message: &[u8],
+) -> Result<()> {
+    let ix = get_instruction_relative(-1, instructions)
+        .map_err(|_| error!(EscrowError::InvalidOrderSignature))?;
+    require_keys_eq!(
+        ix.program_id,
+        ed25519_program::ID,
+        EscrowError::InvalidOrderSignature
+    );
+
+    let data = &ix.data;
+    require!(
+        data.len() >= OFFSETS_START + OFFSETS_LEN && data[0] == 1,
+        EscrowError::InvalidOrderSignature
+    );
+
+    // Ed25519SignatureOffsets is seven little endian u16s
+    let field = |index: usize| {
+        let at = OFFSETS_START + 2 * index;
+        u16::from_le_bytes([data[at], data[at + 1]])
+    };
+    let (signature_offset, signature_ix) = (field(0), field(1));
+    let (pubkey_offset, pubkey_ix) = (field(2), field(3));
+    let (message_offset, message_len, message_ix) = (field(4), field(5), field(6));
+
+    // the signature, key and message must all live in the Ed25519 instruction itself, otherwise
+    // the bytes read below are not the ones that were verified
+    require!(
+        [signature_ix, pubkey_ix, message_ix]
+            .iter()
+            .all(|&index| index == CURRENT_INSTRUCTION),
+        EscrowError::InvalidOrderSignature
+    );
+
+    let slice = |offset: u16, len: usize| {
+        data.get(offset as usize..offset as usize + len)
+            .ok_or(error!(EscrowError::InvalidOrderSignature))
+    };
+    slice(signature_offset, SIGNATURE_LEN)?;
+    require!(
+        slice(pubkey_offset, PUBKEY_LEN)? == signer.as_ref(),
+        EscrowError::InvalidOrderSignature
+    );
+    require!(
+        slice(message_offset, message_len as usize)? == message,
+        EscrowError::InvalidOrderSignature
+    );
+
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use anchor_lang::prelude::*;
+
+use crate::EscrowError;
+
+// fees are expressed in basis points of the amount paid
+pub const FEE_BPS_DENOMINATOR: u16 = 10_000;
+// highest protocol fee the admin may set, 10%
+pub const MAX_FEE_BPS: u16 = 1_000;
+
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct Config {
+    pub admin: Pubkey,
+    pub fee_bps: u16,
+    pub fee_recipient: Pubkey,
+    pub paused: bool,
+    pub bump: u8,
+}
+
+impl Config {
+    // protocol cut of `amount`, rounded down in favour of the maker
+    pub fn fee_for(&self, amount: u64) -> Result<u64> {
+        let fee = (amount as u128)
+            .checked_mul(self.fee_bps as u128)
+            .and_then(|v| v.checked_div(FEE_BPS_DENOMINATOR as u128))
+            .ok_or(EscrowError::MathOverflow)?;
+
+        u64::try_from(fee).map_err(|_| EscrowError::MathOverflow.into())
+    }
+}
@@ -1,8 +1,117 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{prelude::*, solana_program::hash::hash};
 
-#[account]
-#[derive(InitSpace, Debug)]
+use crate::EscrowError;
+
+// layout written by this program, bumped whenever a field is added to `Escrow`
+pub const ESCROW_VERSION: u8 = 1;
+// arbiter splits are expressed in basis points of each vault
+pub const MAX_SPLIT_BPS: u16 = 10_000;
+// extra mints a bundle offer may deposit or ask for on top of mint a and mint b
+pub const MAX_BUNDLE_LEGS: usize = 4;
+
+// one extra mint of a bundle and the amount deposited or asked for
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Debug, PartialEq)]
+pub struct BundleLeg {
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+// `#[account]` without its generated deserializer, see the `AccountDeserialize` impl below
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace, Debug)]
 pub struct Escrow {
+    // first byte after the discriminator so later layouts can be told apart before decoding
+    pub version: u8,
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub receive: u64,
+    pub bump: u8,
+    pub initialization_time: u64,
+    // unix timestamps in seconds, 0 means the bound is not set
+    pub take_not_before: i64,
+    pub expires_at: i64,
+    pub refund_not_before: i64,
+    // when set, only this key may take the offer
+    pub allowed_taker: Option<Pubkey>,
+    // when set, `receive` is what the maker gets after mint b transfer fees
+    pub receive_is_net: bool,
+    // mint a held by the vault after mint a transfer fees
+    pub deposited: u64,
+    // bumped on every amend, so takers can pin the terms they signed against
+    pub revision: u64,
+    // dutch auction, the price decays linearly from `receive` at `auction_start` down to
+    // `end_receive` at `auction_end`, auction_end == 0 means a fixed price offer
+    pub end_receive: u64,
+    pub auction_start: i64,
+    pub auction_end: i64,
+    // lamports held on the escrow above rent, paid to whoever cranks the refund after expiry
+    pub crank_tip: u64,
+    // hash time-locked swap, take must reveal the sha256 preimage and `expires_at` is the timeout
+    pub hashlock: Option<[u8; 32]>,
+    // arbitrated escrow, the taker funds `vault_b` instead of paying the maker and the arbiter
+    // settles both vaults, `taker` is set once funded
+    pub arbiter: Option<Pubkey>,
+    pub taker: Option<Pubkey>,
+    pub disputed: bool,
+    // bundle offer, extra mints deposited next to mint a, each in its own escrow owned vault,
+    // and extra mints asked for next to mint b
+    #[max_len(MAX_BUNDLE_LEGS)]
+    pub bundle_a: Vec<BundleLeg>,
+    #[max_len(MAX_BUNDLE_LEGS)]
+    pub bundle_b: Vec<BundleLeg>,
+    // crowd-filled escrow, takers pool mint b into `vault_b` until `receive` is reached and then
+    // claim their share of the vault, `contributed` is held for takers and `claimed` paid out
+    pub crowd: bool,
+    pub contributed: u64,
+    pub claimed: u64,
+}
+
+impl Discriminator for Escrow {
+    // sha256("account:Escrow")[..8], the discriminator `#[account]` would derive
+    const DISCRIMINATOR: &'static [u8] = &[31, 213, 123, 187, 186, 22, 218, 155];
+}
+
+impl Owner for Escrow {
+    fn owner() -> Pubkey {
+        crate::ID
+    }
+}
+
+impl AccountSerialize for Escrow {
+    fn try_serialize<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
+        writer
+            .write_all(Self::DISCRIMINATOR)
+            .map_err(|_| ErrorCode::AccountDidNotSerialize)?;
+        AnchorSerialize::serialize(self, writer).map_err(|_| ErrorCode::AccountDidNotSerialize)?;
+        Ok(())
+    }
+}
+
+// the version is read before borsh touches the data, so an older layout fails with
+// `UnsupportedEscrowVersion` instead of `AccountDidNotDeserialize` in every context
+impl AccountDeserialize for Escrow {
+    fn try_deserialize(buf: &mut &[u8]) -> Result<Self> {
+        if !buf.starts_with(Self::DISCRIMINATOR) {
+            return Err(error!(ErrorCode::AccountDiscriminatorMismatch).with_account_name("Escrow"));
+        }
+        require!(
+            Self::stored_version(buf)? == ESCROW_VERSION,
+            EscrowError::UnsupportedEscrowVersion
+        );
+        Self::try_deserialize_unchecked(buf)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> Result<Self> {
+        let mut data: &[u8] = &buf[Self::DISCRIMINATOR.len()..];
+        AnchorDeserialize::deserialize(&mut data)
+            .map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+    }
+}
+
+// unversioned layout of the first release, migrated to `ESCROW_VERSION` by `migrate_escrow`
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Debug)]
+pub struct EscrowV0 {
     pub seed: u64,
     pub maker: Pubkey,
     pub mint_a: Pubkey,
@@ -11,3 +120,295 @@ pub struct Escrow {
     pub bump: u8,
     pub initialization_time: u64,
 }
+
+impl EscrowV0 {
+    // a v0 offer is a plain fixed price offer with no time, taker or settlement options
+    pub fn upgrade(self, deposited: u64) -> Escrow {
+        Escrow {
+            version: ESCROW_VERSION,
+            seed: self.seed,
+            maker: self.maker,
+            mint_a: self.mint_a,
+            mint_b: self.mint_b,
+            receive: self.receive,
+            bump: self.bump,
+            initialization_time: self.initialization_time,
+            take_not_before: 0,
+            expires_at: 0,
+            refund_not_before: 0,
+            allowed_taker: None,
+            receive_is_net: false,
+            deposited,
+            revision: 0,
+            end_receive: 0,
+            auction_start: 0,
+            auction_end: 0,
+            crank_tip: 0,
+            hashlock: None,
+            arbiter: None,
+            taker: None,
+            disputed: false,
+            bundle_a: Vec::new(),
+            bundle_b: Vec::new(),
+            crowd: false,
+            contributed: 0,
+            claimed: 0,
+        }
+    }
+}
+
+impl Escrow {
+    /// layout version of raw escrow account data, v0 accounts have no version byte and are
+    /// recognised by their size
+    pub fn stored_version(data: &[u8]) -> Result<u8> {
+        if data.len() == Self::DISCRIMINATOR.len() + EscrowV0::INIT_SPACE {
+            return Ok(0);
+        }
+
+        data.get(Self::DISCRIMINATOR.len())
+            .copied()
+            .ok_or(ErrorCode::AccountDidNotDeserialize.into())
+    }
+
+    pub fn validate_time_policy(&self, now: i64) -> Result<()> {
+        if self.hashlock.is_some() {
+            require!(self.expires_at != 0, EscrowError::InvalidTimeWindow);
+            require!(
+                self.allowed_taker.is_some(),
+                EscrowError::HashlockWithoutTaker
+            );
+        }
+        if self.expires_at != 0 {
+            require!(
+                self.expires_at > now && self.expires_at > self.take_not_before,
+                EscrowError::InvalidTimeWindow
+            );
+        }
+        Ok(())
+    }
+
+    pub fn validate_auction(&self) -> Result<()> {
+        if self.is_auction() {
+            require!(
+                self.auction_start < self.auction_end
+                    && self.end_receive > 0
+                    && self.end_receive <= self.receive,
+                EscrowError::InvalidAuction
+            );
+        } else {
+            require!(
+                self.end_receive == 0 && self.auction_start == 0,
+                EscrowError::InvalidAuction
+            );
+        }
+        Ok(())
+    }
+
+    pub fn is_auction(&self) -> bool {
+        self.auction_end != 0
+    }
+
+    // funding pays `vault_b` gross, so a net ask is not supported on arbitrated escrows
+    pub fn validate_arbiter(&self) -> Result<()> {
+        let Some(arbiter) = self.arbiter else {
+            return Ok(());
+        };
+        require!(
+            arbiter != self.maker
+                && self.allowed_taker != Some(arbiter)
+                && !self.is_auction()
+                && self.hashlock.is_none()
+                && !self.is_bundle()
+                && !self.receive_is_net,
+            EscrowError::InvalidArbiter
+        );
+        Ok(())
+    }
+
+    pub fn validate_bundle(&self) -> Result<()> {
+        for (primary, legs) in [(self.mint_a, &self.bundle_a), (self.mint_b, &self.bundle_b)] {
+            require!(legs.len() <= MAX_BUNDLE_LEGS, EscrowError::InvalidBundle);
+            for (i, leg) in legs.iter().enumerate() {
+                // a repeated mint would share its vault with another leg
+                require!(
+                    leg.amount > 0
+                        && leg.mint != primary
+                        && legs[..i].iter().all(|other| other.mint != leg.mint),
+                    EscrowError::InvalidBundle
+                );
+            }
+        }
+        Ok(())
+    }
+
+    pub fn validate_crowd(&self) -> Result<()> {
+        if self.crowd {
+            require!(
+                self.expires_at != 0
+                    && self.allowed_taker.is_none()
+                    && !self.receive_is_net
+                    && !self.is_auction()
+                    && self.hashlock.is_none()
+                    && self.arbiter.is_none()
+                    && !self.is_bundle(),
+                EscrowError::InvalidCrowd
+            );
+        }
+        Ok(())
+    }
+
+    pub fn is_crowd_filled(&self) -> bool {
+        self.crowd && self.contributed == self.receive
+    }
+
+    pub fn ensure_can_contribute(&self, now: i64, amount: u64) -> Result<()> {
+        require!(self.crowd, EscrowError::NotCrowdEscrow);
+        require!(!self.is_crowd_filled(), EscrowError::CrowdFilled);
+        self.ensure_can_take(now)?;
+        require!(
+            amount > 0 && amount <= self.receive - self.contributed,
+            EscrowError::InvalidTakeAmount
+        );
+        Ok(())
+    }
+
+    pub fn ensure_can_refund_contribution(&self, now: i64) -> Result<()> {
+        require!(!self.is_crowd_filled(), EscrowError::CrowdFilled);
+        require!(now >= self.expires_at, EscrowError::OfferNotExpired);
+        Ok(())
+    }
+
+    /// share of `vault_amount` owed for `amount` of mint b out of `total`, the last one out
+    /// takes whatever is left so the vault can be closed
+    pub fn crowd_share(vault_amount: u64, amount: u64, total: u64) -> Result<u64> {
+        if amount == total {
+            return Ok(vault_amount);
+        }
+
+        let share = (vault_amount as u128)
+            .checked_mul(amount as u128)
+            .and_then(|v| v.checked_div(total as u128))
+            .ok_or(EscrowError::MathOverflow)?;
+
+        u64::try_from(share).map_err(|_| EscrowError::MathOverflow.into())
+    }
+
+    pub fn is_bundle(&self) -> bool {
+        !self.bundle_a.is_empty() || !self.bundle_b.is_empty()
+    }
+
+    pub fn is_party(&self, key: &Pubkey) -> bool {
+        *key == self.maker || self.taker == Some(*key)
+    }
+
+    // the arbiter may settle at any split, a party may only concede everything to the other side
+    // and only while no dispute is open
+    pub fn can_release(&self, signer: &Pubkey, taker_bps: u16) -> bool {
+        if self.arbiter == Some(*signer) {
+            return true;
+        }
+        !self.disputed
+            && ((*signer == self.maker && taker_bps == MAX_SPLIT_BPS)
+                || (self.taker == Some(*signer) && taker_bps == 0))
+    }
+
+    pub fn ensure_can_fund(&self, now: i64) -> Result<()> {
+        require!(self.arbiter.is_some(), EscrowError::InvalidArbiter);
+        self.ensure_unlocked()?;
+        self.ensure_can_take(now)
+    }
+
+    // once a taker has funded or contributed, neither side can pull out or change terms on its own
+    pub fn ensure_unlocked(&self) -> Result<()> {
+        require!(
+            self.taker.is_none() && !self.disputed && self.contributed == 0,
+            EscrowError::EscrowLocked
+        );
+        Ok(())
+    }
+
+    pub fn is_partially_fillable(&self) -> bool {
+        !self.is_auction() && self.hashlock.is_none() && !self.is_bundle()
+    }
+
+    /// asked amount of mint b at `now`, the decay is rounded down in favour of the maker
+    pub fn current_receive(&self, now: i64) -> Result<u64> {
+        if !self.is_auction() || now <= self.auction_start {
+            return Ok(self.receive);
+        }
+        if now >= self.auction_end {
+            return Ok(self.end_receive);
+        }
+
+        let decay = (self.receive - self.end_receive) as u128;
+        let elapsed = now
+            .checked_sub(self.auction_start)
+            .ok_or(EscrowError::MathOverflow)? as u128;
+        let duration = self
+            .auction_end
+            .checked_sub(self.auction_start)
+            .ok_or(EscrowError::MathOverflow)? as u128;
+
+        let drop = decay
+            .checked_mul(elapsed)
+            .and_then(|v| v.checked_div(duration))
+            .ok_or(EscrowError::MathOverflow)?;
+
+        // drop <= decay <= receive, so the subtraction cannot underflow
+        Ok(self.receive - drop as u64)
+    }
+
+    pub fn can_be_taken_by(&self, taker: &Pubkey) -> bool {
+        self.allowed_taker.is_none_or(|allowed| allowed == *taker)
+    }
+
+    pub fn ensure_can_take(&self, now: i64) -> Result<()> {
+        require!(
+            now >= self.take_not_before,
+            EscrowError::TooEarlyToTakeOffer
+        );
+        require!(
+            self.expires_at == 0 || now < self.expires_at,
+            EscrowError::OfferExpired
+        );
+        Ok(())
+    }
+
+    pub fn ensure_expired(&self, now: i64) -> Result<()> {
+        require!(
+            self.expires_at != 0 && now >= self.expires_at,
+            EscrowError::OfferNotExpired
+        );
+        self.ensure_unlocked()
+    }
+
+    pub fn ensure_preimage(&self, preimage: Option<&[u8]>) -> Result<()> {
+        let Some(hashlock) = self.hashlock else {
+            return Ok(());
+        };
+        require!(
+            preimage.is_some_and(|preimage| hash(preimage).to_bytes() == hashlock),
+            EscrowError::InvalidPreimage
+        );
+        Ok(())
+    }
+
+    pub fn ensure_revision(&self, expected_revision: Option<u64>) -> Result<()> {
+        require!(
+            expected_revision.is_none_or(|revision| revision == self.revision),
+            EscrowError::RevisionMismatch
+        );
+        Ok(())
+    }
+
+    pub fn ensure_can_refund(&self, now: i64) -> Result<()> {
+        self.ensure_unlocked()?;
+        require!(now >= self.refund_not_before, EscrowError::TooEarlyToRefund);
+        // a hashlocked offer stays claimable by the taker until its timeout
+        require!(
+            self.hashlock.is_none() || now >= self.expires_at,
+            EscrowError::TooEarlyToRefund
+        );
+        Ok(())
+    }
+}
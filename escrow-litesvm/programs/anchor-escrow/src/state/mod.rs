@@ -1,3 +1,9 @@
+pub mod config;
 pub mod escrow;
+pub mod order;
+pub mod receipt;
 
-pub use escrow::*;
\ No newline at end of file
+pub use config::*;
+pub use escrow::*;
+pub use order::*;
+pub use receipt::*;
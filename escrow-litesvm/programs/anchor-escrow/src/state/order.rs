@@ -0,0 +1,59 @@
+use anchor_lang::prelude::*;
+
+use crate::EscrowError;
+
+// prefixed to every signed order so the signature cannot be replayed as some other message
+pub const ORDER_DOMAIN: &[u8] = b"anchor-escrow:order";
+
+// terms the maker signs off-chain, filled in full by `fill_order` out of the maker's own
+// token account through the program's delegate
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct Order {
+    pub maker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    // one order per nonce, see `OrderNonce`
+    pub nonce: u64,
+    // unix timestamp in seconds, 0 means the order does not expire
+    pub expires_at: i64,
+    // when set, only this key may fill the order
+    pub allowed_taker: Option<Pubkey>,
+}
+
+impl Order {
+    /// bytes the maker signs: the domain, this program's id and the borsh encoded order
+    pub fn message(&self) -> Result<Vec<u8>> {
+        let mut message = [ORDER_DOMAIN, crate::ID.as_ref()].concat();
+        self.serialize(&mut message)?;
+        Ok(message)
+    }
+
+    pub fn ensure_can_fill(&self, now: i64, taker: &Pubkey) -> Result<()> {
+        require!(
+            self.expires_at == 0 || now < self.expires_at,
+            EscrowError::OfferExpired
+        );
+        require!(
+            self.allowed_taker.is_none_or(|allowed| allowed == *taker),
+            EscrowError::UnauthorizedTaker
+        );
+        require!(
+            self.amount_a > 0 && self.amount_b > 0,
+            EscrowError::InvalidOrder
+        );
+        Ok(())
+    }
+}
+
+// marks a maker's order nonce as spent, seeds = [b"order", maker, nonce]
+// created by the fill, or up front by the maker to cancel the order
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct OrderNonce {
+    pub maker: Pubkey,
+    pub nonce: u64,
+    pub cancelled: bool,
+    pub bump: u8,
+}
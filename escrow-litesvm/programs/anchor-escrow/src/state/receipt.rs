@@ -0,0 +1,11 @@
+use anchor_lang::prelude::*;
+
+// mint b a taker has put into a crowd-filled escrow, seeds = [b"receipt", escrow, taker]
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct Receipt {
+    pub escrow: Pubkey,
+    pub taker: Pubkey,
+    pub amount: u64,
+    pub bump: u8,
+}
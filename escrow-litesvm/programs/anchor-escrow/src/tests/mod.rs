@@ -3,37 +3,49 @@ mod tests {
 
     use {
         crate::{
-            accounts::{Take, TakeAfterTime},
-            instruction,
+            instructions::EscrowError,
+            state::{Escrow, EscrowV0},
         },
+        anchor_escrow_client::{self as client, BundleLeg, MakeArgs, Offer, Order, ESCROW_VERSION},
         anchor_lang::{
             prelude::{msg, Clock},
-            solana_program::program_pack::Pack,
-            AccountDeserialize, InstructionData, Key, ToAccountMetas,
+            solana_program::{
+                bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+                hash::hash,
+                instruction::InstructionError,
+                program_pack::Pack,
+            },
+            AnchorDeserialize, AnchorSerialize, Discriminator,
         },
         anchor_spl::{
             associated_token::{self, spl_associated_token_account},
             token::spl_token,
+            token_2022::spl_token_2022::{
+                self,
+                extension::{ExtensionType, StateWithExtensions},
+            },
         },
-        litesvm::LiteSVM,
+        litesvm::{types::TransactionResult, LiteSVM},
         litesvm_token::{
             spl_token::ID as TOKEN_PROGRAM_ID, CreateAssociatedTokenAccount, CreateMint, MintTo,
         },
         solana_account::Account,
-        solana_address::Address,
-        solana_instruction::Instruction,
+        solana_instruction::{AccountMeta, Instruction},
         solana_keypair::Keypair,
         solana_message::Message,
         solana_native_token::LAMPORTS_PER_SOL,
         solana_pubkey::Pubkey,
-        solana_rpc_client::rpc_client::RpcClient,
-        solana_sdk_ids::system_program::{self, ID as SYSTEM_PROGRAM_ID},
+        solana_rpc_client_api::response::RpcKeyedAccount,
+        solana_sdk_ids::system_program,
         solana_signer::Signer,
         solana_transaction::Transaction,
+        solana_transaction_error::TransactionError,
         std::{path::PathBuf, str::FromStr},
     };
 
     static PROGRAM_ID: Pubkey = crate::ID;
+    const TRANSFER_HOOK_PROGRAM_ID: Pubkey =
+        Pubkey::from_str_const("DhzyDgCmmQzVC4vEcj2zRGUyN8Mt5JynfdGLKkBcRGaX");
     const TOKEN_DECIMALS: u8 = 6;
     const DECIMALS_PER_TOKEN: u64 = 1000_000;
 
@@ -47,39 +59,311 @@ mod tests {
             .airdrop(&payer.pubkey(), 10 * LAMPORTS_PER_SOL)
             .expect("Failed to airdrop SOL to payer");
 
-        // Load program SO file
+        // Deploy the program with the payer as its upgrade authority
+        deploy_program(&mut program, &payer.pubkey());
+
+        // Example on how to load an account snapshot, `synthetic_payer` is a hand-written funded
+        // system account under a made up key, real snapshots are captured with
+        // `cargo run --example capture_fixture -- <address> --name <name>`
+        let (_, fixture_account) = read_fixture("synthetic_payer");
+        let lamports = fixture_account.lamports;
+
+        program
+            .set_account(payer.pubkey(), fixture_account)
+            .unwrap();
+
+        msg!("Lamports of fixture account: {}", lamports);
+
+        // Initialize the program config with the payer as admin and no protocol fee
+        let init_config_ix = client::init_config_ix(&payer.pubkey(), 0, &Keypair::new().pubkey());
+        let message = Message::new(&[init_config_ix], Some(&payer.pubkey()));
+        let recent_blockhash = program.latest_blockhash();
+        let transaction = Transaction::new(&[&payer], message, recent_blockhash);
+        program
+            .send_transaction(transaction)
+            .expect("Failed to initialize config");
+
+        // Return the LiteSVM instance and payer keypair
+        (program, payer)
+    }
+
+    /// Deploys the program SO file through the upgradeable loader, so `init_config` can check
+    /// `upgrade_authority` against its `ProgramData` account
+    fn deploy_program(svm: &mut LiteSVM, upgrade_authority: &Pubkey) {
         let so_path =
             PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../target/deploy/anchor_escrow.so");
 
-        let program_data = std::fs::read(so_path).expect("Failed to read program SO file");
+        let program_bytes = std::fs::read(so_path).expect("Failed to read program SO file");
+
+        let program_data = client::pda::program_data();
+        let metadata_len = UpgradeableLoaderState::size_of_programdata_metadata();
+        let mut program_data_account = Account {
+            lamports: svm.minimum_balance_for_rent_exemption(metadata_len + program_bytes.len()),
+            data: vec![0; metadata_len + program_bytes.len()],
+            owner: bpf_loader_upgradeable::ID,
+            executable: false,
+            rent_epoch: 0,
+        };
+        program_data_account
+            .serialize_data(&UpgradeableLoaderState::ProgramData {
+                slot: 0,
+                upgrade_authority_address: Some(*upgrade_authority),
+            })
+            .unwrap();
+        program_data_account.data[metadata_len..].copy_from_slice(&program_bytes);
+        svm.set_account(program_data, program_data_account).unwrap();
+
+        let mut program_account = Account {
+            lamports: svm
+                .minimum_balance_for_rent_exemption(UpgradeableLoaderState::size_of_program()),
+            data: vec![0; UpgradeableLoaderState::size_of_program()],
+            owner: bpf_loader_upgradeable::ID,
+            executable: true,
+            rent_epoch: 0,
+        };
+        program_account
+            .serialize_data(&UpgradeableLoaderState::Program {
+                programdata_address: program_data,
+            })
+            .unwrap();
+        svm.set_account(PROGRAM_ID, program_account).unwrap();
+    }
+
+    /// Asserts the transaction was rejected by the escrow program with `error`
+    fn assert_escrow_error(result: TransactionResult, error: EscrowError) {
+        let failed = result.expect_err("Transaction should have failed");
+        match failed.err {
+            TransactionError::InstructionError(_, InstructionError::Custom(code)) => {
+                assert_eq!(code, u32::from(error), "Unexpected escrow error");
+            }
+            err => panic!("Expected an escrow error, got {err:?}"),
+        }
+    }
+
+    /// Reads `fixtures/<name>.json`, an account snapshot in the `solana account --output json`
+    /// layout as written by `capture_fixture`, returning its address and contents
+    fn read_fixture(name: &str) -> (Pubkey, Account) {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join(format!("{name}.json"));
+        let json = std::fs::read_to_string(&path)
+            .unwrap_or_else(|_| panic!("Failed to read fixture {}", path.display()));
+        let fixture: RpcKeyedAccount = serde_json::from_str(&json).expect("Malformed fixture");
 
-        program.add_program(PROGRAM_ID, &program_data);
+        let account = &fixture.account;
+        (
+            Pubkey::from_str(&fixture.pubkey).unwrap(),
+            Account {
+                lamports: account.lamports,
+                data: account
+                    .data
+                    .decode()
+                    .expect("Fixture data is not base58/base64"),
+                owner: Pubkey::from_str(&account.owner).unwrap(),
+                executable: account.executable,
+                rent_epoch: account.rent_epoch,
+            },
+        )
+    }
 
-        // Example on how to Load an account from devnet
-        let rpc_client = RpcClient::new("https://api.devnet.solana.com");
-        let account_address =
-            Address::from_str("DRYvf71cbF2s5wgaJQvAGkghMkRcp5arvsK2w97vXhi2").unwrap();
-        let fetched_account = rpc_client
-            .get_account(&account_address)
-            .expect("Failed to fetch account from devnet");
+    /// Token-2022 mint charging `fee_bps` on every transfer
+    fn create_transfer_fee_mint(svm: &mut LiteSVM, authority: &Keypair, fee_bps: u16) -> Pubkey {
+        let mint = Keypair::new();
+        let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+            ExtensionType::TransferFeeConfig,
+        ])
+        .unwrap();
 
-        program
-            .set_account(
-                payer.pubkey(),
-                Account {
-                    lamports: fetched_account.lamports,
-                    data: fetched_account.data,
-                    owner: Pubkey::from(fetched_account.owner.to_bytes()),
-                    executable: fetched_account.executable,
-                    rent_epoch: fetched_account.rent_epoch,
-                },
+        let instructions = [
+            anchor_lang::solana_program::system_instruction::create_account(
+                &authority.pubkey(),
+                &mint.pubkey(),
+                svm.minimum_balance_for_rent_exemption(space),
+                space as u64,
+                &spl_token_2022::ID,
+            ),
+            spl_token_2022::extension::transfer_fee::instruction::initialize_transfer_fee_config(
+                &spl_token_2022::ID,
+                &mint.pubkey(),
+                Some(&authority.pubkey()),
+                Some(&authority.pubkey()),
+                fee_bps,
+                u64::MAX,
             )
-            .unwrap();
+            .unwrap(),
+            spl_token_2022::instruction::initialize_mint2(
+                &spl_token_2022::ID,
+                &mint.pubkey(),
+                &authority.pubkey(),
+                None,
+                TOKEN_DECIMALS,
+            )
+            .unwrap(),
+        ];
+        let message = Message::new(&instructions, Some(&authority.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[authority, &mint], message, recent_blockhash);
+        svm.send_transaction(tx).unwrap();
 
-        msg!("Lamports of fetched account: {}", fetched_account.lamports);
+        mint.pubkey()
+    }
 
-        // Return the LiteSVM instance and payer keypair
-        (program, payer)
+    /// Token-2022 associated token account of `owner`, optionally funded by the mint authority
+    fn create_token_2022_ata(
+        svm: &mut LiteSVM,
+        authority: &Keypair,
+        owner: &Pubkey,
+        mint: &Pubkey,
+        amount: u64,
+    ) -> Pubkey {
+        let ata = associated_token::get_associated_token_address_with_program_id(
+            owner,
+            mint,
+            &spl_token_2022::ID,
+        );
+
+        let mut instructions = vec![
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &authority.pubkey(),
+                owner,
+                mint,
+                &spl_token_2022::ID,
+            ),
+        ];
+        if amount > 0 {
+            instructions.push(
+                spl_token_2022::instruction::mint_to(
+                    &spl_token_2022::ID,
+                    mint,
+                    &ata,
+                    &authority.pubkey(),
+                    &[],
+                    amount,
+                )
+                .unwrap(),
+            );
+        }
+        let message = Message::new(&instructions, Some(&authority.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[authority], message, recent_blockhash);
+        svm.send_transaction(tx).unwrap();
+
+        ata
+    }
+
+    /// Loads the repo's whitelist transfer hook, which only lets whitelisted owners send tokens
+    fn load_transfer_hook(svm: &mut LiteSVM) {
+        let so_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../../../whitelist-transfer-hook/target/deploy/whitelist_transfer_hook.so");
+
+        let program_data =
+            std::fs::read(so_path).expect("Failed to read transfer hook program SO file");
+
+        svm.add_program(TRANSFER_HOOK_PROGRAM_ID, &program_data);
+    }
+
+    /// Token-2022 mint whose transfers run the whitelist hook, with its `ExtraAccountMetaList`
+    /// initialized
+    fn create_transfer_hook_mint(svm: &mut LiteSVM, authority: &Keypair) -> Pubkey {
+        let mint = Keypair::new();
+        let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+            ExtensionType::TransferHook,
+        ])
+        .unwrap();
+
+        let instructions = [
+            anchor_lang::solana_program::system_instruction::create_account(
+                &authority.pubkey(),
+                &mint.pubkey(),
+                svm.minimum_balance_for_rent_exemption(space),
+                space as u64,
+                &spl_token_2022::ID,
+            ),
+            spl_token_2022::extension::transfer_hook::instruction::initialize(
+                &spl_token_2022::ID,
+                &mint.pubkey(),
+                Some(authority.pubkey()),
+                Some(TRANSFER_HOOK_PROGRAM_ID),
+            )
+            .unwrap(),
+            spl_token_2022::instruction::initialize_mint2(
+                &spl_token_2022::ID,
+                &mint.pubkey(),
+                &authority.pubkey(),
+                None,
+                TOKEN_DECIMALS,
+            )
+            .unwrap(),
+            // the hook's own initialize_transfer_hook, keyed by the interface discriminator
+            Instruction {
+                program_id: TRANSFER_HOOK_PROGRAM_ID,
+                accounts: vec![
+                    AccountMeta::new(authority.pubkey(), true),
+                    AccountMeta::new(extra_account_metas(&mint.pubkey()), false),
+                    AccountMeta::new_readonly(mint.pubkey(), false),
+                    AccountMeta::new_readonly(system_program::ID, false),
+                ],
+                data: hash(b"spl-transfer-hook-interface:initialize-extra-account-metas")
+                    .to_bytes()[..8]
+                    .to_vec(),
+            },
+        ];
+        let message = Message::new(&instructions, Some(&authority.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[authority, &mint], message, recent_blockhash);
+        svm.send_transaction(tx).unwrap();
+
+        mint.pubkey()
+    }
+
+    /// Lets `owner` send tokens of every whitelist hook mint
+    fn whitelist(svm: &mut LiteSVM, payer: &Keypair, owner: &Pubkey) {
+        let mut data = hash(b"global:add_to_whitelist").to_bytes()[..8].to_vec();
+        data.extend_from_slice(owner.as_ref());
+
+        let add_to_whitelist_ix = Instruction {
+            program_id: TRANSFER_HOOK_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new(whitelist_entry(owner), false),
+                AccountMeta::new_readonly(system_program::ID, false),
+            ],
+            data,
+        };
+        let message = Message::new(&[add_to_whitelist_ix], Some(&payer.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[payer], message, recent_blockhash);
+        svm.send_transaction(tx).unwrap();
+    }
+
+    fn extra_account_metas(mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"extra-account-metas", mint.as_ref()],
+            &TRANSFER_HOOK_PROGRAM_ID,
+        )
+        .0
+    }
+
+    fn whitelist_entry(owner: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"whitelist", owner.as_ref()], &TRANSFER_HOOK_PROGRAM_ID).0
+    }
+
+    /// Extra accounts the hook needs for a transfer of `mint` out of `owner`'s token account,
+    /// appended to an escrow instruction's remaining accounts
+    fn transfer_hook_accounts(mint: &Pubkey, owner: &Pubkey) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(extra_account_metas(mint), false),
+            AccountMeta::new_readonly(whitelist_entry(owner), false),
+            AccountMeta::new_readonly(TRANSFER_HOOK_PROGRAM_ID, false),
+        ]
+    }
+
+    fn token_2022_balance(svm: &LiteSVM, ata: &Pubkey) -> u64 {
+        let account = svm.get_account(ata).unwrap();
+        StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+            .unwrap()
+            .base
+            .amount
     }
 
     #[test]
@@ -112,50 +396,21 @@ mod tests {
             .unwrap();
         msg!("Maker ATA A: {}\n", maker_ata_a);
 
-        // Derive the PDA for the escrow account using the maker's public key and a seed value
-        let escrow = Pubkey::find_program_address(
-            &[b"escrow", maker.as_ref(), &123u64.to_le_bytes()],
-            &PROGRAM_ID,
-        )
-        .0;
+        // Describe the offer, the client derives the escrow PDA and its vault from it
+        let offer = Offer::new(maker, 123, mint_a, mint_b);
+        let escrow = offer.escrow();
         msg!("Escrow PDA: {}\n", escrow);
 
-        // Derive the PDA for the vault associated token account using the escrow PDA and Mint A
-        let vault = associated_token::get_associated_token_address(&escrow, &mint_a);
+        let vault = offer.vault();
         msg!("Vault PDA: {}\n", vault);
 
-        // Define program IDs for associated token program, token program, and system program
-        let asspciated_token_program = spl_associated_token_account::ID;
-        let token_program = TOKEN_PROGRAM_ID;
-        let system_program = SYSTEM_PROGRAM_ID;
-
         // Mint 1,000 tokens (with 6 decimal places) of Mint A to the maker's associated token account
         MintTo::new(&mut program, &payer, &mint_a, &maker_ata_a, 1000000000)
             .send()
             .unwrap();
 
         // Create the "Make" instruction to deposit tokens into the escrow
-        let make_ix = Instruction {
-            program_id: PROGRAM_ID,
-            accounts: crate::accounts::Make {
-                maker: maker,
-                mint_a: mint_a,
-                mint_b: mint_b,
-                maker_ata_a: maker_ata_a,
-                escrow: escrow,
-                vault: vault,
-                associated_token_program: asspciated_token_program,
-                token_program: token_program,
-                system_program: system_program,
-            }
-            .to_account_metas(None),
-            data: crate::instruction::Make {
-                deposit: 10,
-                seed: 123u64,
-                receive: 10,
-            }
-            .data(),
-        };
+        let make_ix = client::make_ix(&offer, 10, 10, client::MakeArgs::default());
 
         // Create and send the transaction containing the "Make" instruction
         let message = Message::new(&[make_ix], Some(&payer.pubkey()));
@@ -179,13 +434,26 @@ mod tests {
         assert_eq!(vault_data.mint, mint_a);
 
         let escrow_account = program.get_account(&escrow).unwrap();
-        let escrow_data =
-            crate::state::Escrow::try_deserialize(&mut escrow_account.data.as_ref()).unwrap();
+        let escrow_data = client::decode_escrow(&escrow_account.data).unwrap();
         assert_eq!(escrow_data.seed, 123u64);
         assert_eq!(escrow_data.maker, maker);
         assert_eq!(escrow_data.mint_a, mint_a);
         assert_eq!(escrow_data.mint_b, mint_b);
         assert_eq!(escrow_data.receive, 10);
+
+        // Verify the EscrowMade event emitted through the self-CPI
+        let event_data = tx
+            .inner_instructions
+            .iter()
+            .flatten()
+            .map(|ix| &ix.instruction.data)
+            .find(|data| data.starts_with(anchor_lang::event::EVENT_IX_TAG_LE))
+            .expect("EscrowMade event not emitted");
+        let event = crate::events::EscrowMade::try_from_slice(&event_data[16..]).unwrap();
+        assert_eq!(event.seed, 123u64);
+        assert_eq!(event.maker, maker);
+        assert_eq!(event.deposited, 10);
+        assert_eq!(event.receive, 10);
     }
 
     #[derive(Debug)]
@@ -200,6 +468,9 @@ mod tests {
         escrow: Pubkey,
         vault: Pubkey,
         escrow_seed: u64,
+        offer: Offer,
+        fee_recipient: Pubkey,
+        treasury_ata_b: Pubkey,
     }
 
     impl TestValues {
@@ -238,16 +509,16 @@ mod tests {
                 .send()
                 .unwrap();
             let escrow_seed = 1234u64;
-            let escrow = Pubkey::find_program_address(
-                &[
-                    b"escrow",
-                    maker.pubkey().key().as_ref(),
-                    &escrow_seed.to_le_bytes(),
-                ],
-                &PROGRAM_ID,
-            )
-            .0;
-            let vault = associated_token::get_associated_token_address(&escrow, &mint_a);
+            let offer = Offer::new(maker.pubkey(), escrow_seed, mint_a, mint_b);
+            let escrow = offer.escrow();
+            let vault = offer.vault();
+
+            let config_account = svm.get_account(&client::pda::config()).unwrap();
+            let fee_recipient = client::decode_config(&config_account.data)
+                .unwrap()
+                .fee_recipient;
+            let treasury_ata_b =
+                associated_token::get_associated_token_address(&fee_recipient, &mint_b);
 
             MintTo::new(
                 svm,
@@ -279,6 +550,9 @@ mod tests {
                 escrow,
                 vault,
                 escrow_seed,
+                offer,
+                fee_recipient,
+                treasury_ata_b,
             }
         }
     }
@@ -288,27 +562,7 @@ mod tests {
         let (mut svm, maker) = setup();
         let test_values = TestValues::new(&mut svm, &maker);
         //make
-        let make_ix = Instruction {
-            program_id: PROGRAM_ID,
-            accounts: crate::accounts::Make {
-                maker: maker.pubkey(),
-                mint_a: test_values.mint_a,
-                mint_b: test_values.mint_b,
-                maker_ata_a: test_values.maker_ata_a,
-                escrow: test_values.escrow,
-                vault: test_values.vault,
-                associated_token_program: spl_associated_token_account::ID,
-                token_program: TOKEN_PROGRAM_ID,
-                system_program: system_program::ID,
-            }
-            .to_account_metas(None),
-            data: instruction::Make {
-                deposit: 10,
-                seed: test_values.escrow_seed,
-                receive: 10,
-            }
-            .data(),
-        };
+        let make_ix = client::make_ix(&test_values.offer, 10, 10, MakeArgs::default());
 
         let message = Message::new(&[make_ix], Some(&maker.pubkey()));
         let recent_blockhash = svm.latest_blockhash();
@@ -318,25 +572,13 @@ mod tests {
         msg!("Make transaction successful: {:?}", tx_sig.signature);
 
         //take offer by the taker
-        let take_ix = Instruction {
-            program_id: PROGRAM_ID,
-            accounts: Take {
-                associated_token_program: spl_associated_token_account::ID,
-                escrow: test_values.escrow,
-                maker: maker.pubkey(),
-                maker_ata_b: test_values.maker_ata_b,
-                mint_a: test_values.mint_a,
-                mint_b: test_values.mint_b,
-                system_program: system_program::ID,
-                taker: test_values.taker.pubkey(),
-                taker_ata_a: test_values.taker_ata_a,
-                taker_ata_b: test_values.taker_ata_b,
-                token_program: TOKEN_PROGRAM_ID,
-                vault: test_values.vault,
-            }
-            .to_account_metas(None),
-            data: instruction::Take {}.data(),
-        };
+        let take_ix = client::take_ix(
+            &test_values.offer,
+            &test_values.taker.pubkey(),
+            &test_values.fee_recipient,
+            client::TakeArgs::new(10, 10),
+            None,
+        );
         let take_message = Message::new(&[take_ix], Some(&test_values.taker.pubkey()));
         let recent_blockhash = svm.latest_blockhash();
 
@@ -346,31 +588,11 @@ mod tests {
     }
 
     #[test]
-    fn test_refund() {
+    fn test_take_slippage_guard() {
         let (mut svm, maker) = setup();
         let test_values = TestValues::new(&mut svm, &maker);
         //make
-        let make_ix = Instruction {
-            program_id: PROGRAM_ID,
-            accounts: crate::accounts::Make {
-                maker: maker.pubkey(),
-                mint_a: test_values.mint_a,
-                mint_b: test_values.mint_b,
-                maker_ata_a: test_values.maker_ata_a,
-                escrow: test_values.escrow,
-                vault: test_values.vault,
-                associated_token_program: spl_associated_token_account::ID,
-                token_program: TOKEN_PROGRAM_ID,
-                system_program: system_program::ID,
-            }
-            .to_account_metas(None),
-            data: instruction::Make {
-                deposit: 10,
-                seed: test_values.escrow_seed,
-                receive: 10,
-            }
-            .data(),
-        };
+        let make_ix = client::make_ix(&test_values.offer, 10, 10, MakeArgs::default());
 
         let message = Message::new(&[make_ix], Some(&maker.pubkey()));
         let recent_blockhash = svm.latest_blockhash();
@@ -379,56 +601,74 @@ mod tests {
         let tx_sig = svm.send_transaction(tx).unwrap();
         msg!("Make transaction successful: {:?}", tx_sig.signature);
 
-        //refund
-        let refund_ix = Instruction {
-            program_id: PROGRAM_ID,
-            accounts: crate::accounts::Refund {
-                escrow: test_values.escrow,
-                maker: maker.pubkey(),
-                maker_ata_a: test_values.maker_ata_a,
-                mint_a: test_values.mint_a,
-                system_program: system_program::ID,
-                token_program: spl_token::ID,
-                vault: test_values.vault,
-            }
-            .to_account_metas(None),
-            data: instruction::Refund {}.data(),
-        };
-        let message = Message::new(&[refund_ix], Some(&maker.pubkey()));
+        //asking for more mint a than the vault holds fails
+        let take_ix = client::take_ix(
+            &test_values.offer,
+            &test_values.taker.pubkey(),
+            &test_values.fee_recipient,
+            client::TakeArgs::new(10, 11),
+            None,
+        );
+        let take_message = Message::new(&[take_ix], Some(&test_values.taker.pubkey()));
         let recent_blockhash = svm.latest_blockhash();
-        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        let tx = Transaction::new(&[&test_values.taker], take_message, recent_blockhash);
+        assert_escrow_error(svm.send_transaction(tx), EscrowError::SlippageExceeded);
+        msg!("Take below min_amount_a failed");
+
+        //paying less mint b than the escrow asks fails
+        let take_ix = client::take_ix(
+            &test_values.offer,
+            &test_values.taker.pubkey(),
+            &test_values.fee_recipient,
+            client::TakeArgs::new(9, 10),
+            None,
+        );
+        let take_message = Message::new(&[take_ix], Some(&test_values.taker.pubkey()));
+        svm.expire_blockhash();
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&test_values.taker], take_message, recent_blockhash);
+        assert_escrow_error(svm.send_transaction(tx), EscrowError::SlippageExceeded);
+        msg!("Take above expected_receive failed");
+
+        //take within bounds
+        let take_ix = client::take_ix(
+            &test_values.offer,
+            &test_values.taker.pubkey(),
+            &test_values.fee_recipient,
+            client::TakeArgs::new(10, 10),
+            None,
+        );
+        let take_message = Message::new(&[take_ix], Some(&test_values.taker.pubkey()));
+        svm.expire_blockhash();
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&test_values.taker], take_message, recent_blockhash);
         let tx_sig = svm.send_transaction(tx).unwrap();
-        msg!("Refund successfull with tx sign: {:?}", tx_sig.signature);
+        msg!("Take transaction successful: {:?}", tx_sig.signature);
     }
 
     #[test]
-    fn take_after_time() {
+    fn test_take_with_protocol_fee() {
         let (mut svm, maker) = setup();
         let test_values = TestValues::new(&mut svm, &maker);
-        let mut initial_time = svm.get_sysvar::<Clock>();
-        println!("initial time: {}", initial_time.unix_timestamp);
+
+        //fees above the 10% cap are rejected
+        let update_config_ix =
+            client::update_config_ix(&maker.pubkey(), None, Some(1_001), None, None);
+        let message = Message::new(&[update_config_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        assert_escrow_error(svm.send_transaction(tx), EscrowError::InvalidFee);
+
+        //set a 1% protocol fee, maker is the config admin
+        let update_config_ix =
+            client::update_config_ix(&maker.pubkey(), None, Some(100), None, None);
+        let message = Message::new(&[update_config_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        svm.send_transaction(tx).unwrap();
+
         //make
-        let make_ix = Instruction {
-            program_id: PROGRAM_ID,
-            accounts: crate::accounts::Make {
-                maker: maker.pubkey(),
-                mint_a: test_values.mint_a,
-                mint_b: test_values.mint_b,
-                maker_ata_a: test_values.maker_ata_a,
-                escrow: test_values.escrow,
-                vault: test_values.vault,
-                associated_token_program: spl_associated_token_account::ID,
-                token_program: TOKEN_PROGRAM_ID,
-                system_program: system_program::ID,
-            }
-            .to_account_metas(None),
-            data: instruction::Make {
-                deposit: 10,
-                seed: test_values.escrow_seed,
-                receive: 10,
-            }
-            .data(),
-        };
+        let make_ix = client::make_ix(&test_values.offer, 10, 1000, MakeArgs::default());
 
         let message = Message::new(&[make_ix], Some(&maker.pubkey()));
         let recent_blockhash = svm.latest_blockhash();
@@ -438,99 +678,198 @@ mod tests {
         msg!("Make transaction successful: {:?}", tx_sig.signature);
 
         //take offer by the taker
-        let take_ix = Instruction {
-            program_id: PROGRAM_ID,
-            accounts: TakeAfterTime {
-                associated_token_program: spl_associated_token_account::ID,
-                escrow: test_values.escrow,
-                maker: maker.pubkey(),
-                maker_ata_b: test_values.maker_ata_b,
-                mint_a: test_values.mint_a,
-                mint_b: test_values.mint_b,
-                system_program: system_program::ID,
-                taker: test_values.taker.pubkey(),
-                taker_ata_a: test_values.taker_ata_a,
-                taker_ata_b: test_values.taker_ata_b,
-                token_program: TOKEN_PROGRAM_ID,
-                vault: test_values.vault,
-            }
-            .to_account_metas(None),
-            data: instruction::TakeAfterTime {}.data(),
-        };
+        let take_ix = client::take_ix(
+            &test_values.offer,
+            &test_values.taker.pubkey(),
+            &test_values.fee_recipient,
+            client::TakeArgs::new(1000, 10),
+            None,
+        );
         let take_message = Message::new(&[take_ix], Some(&test_values.taker.pubkey()));
         let recent_blockhash = svm.latest_blockhash();
 
         let tx = Transaction::new(&[&test_values.taker], take_message, recent_blockhash);
-        let tx_sig = svm.send_transaction(tx);
-        println!("tx_sig: {:?}", tx_sig);
-        assert!(tx_sig.is_err());
-        msg!("Take transaction before time failed");
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Take transaction successful: {:?}", tx_sig.signature);
 
-        initial_time.unix_timestamp = initial_time.unix_timestamp.saturating_add(60 * 15 * 10000);
-        svm.set_sysvar::<Clock>(&initial_time);
-        println!("time set to {}", svm.get_sysvar::<Clock>().unix_timestamp);
+        let maker_ata_b = svm.get_account(&test_values.maker_ata_b).unwrap();
+        let maker_ata_b_data = spl_token::state::Account::unpack(&maker_ata_b.data).unwrap();
+        assert_eq!(maker_ata_b_data.amount, 990);
+
+        let treasury_ata_b = svm.get_account(&test_values.treasury_ata_b).unwrap();
+        let treasury_ata_b_data = spl_token::state::Account::unpack(&treasury_ata_b.data).unwrap();
+        assert_eq!(treasury_ata_b_data.amount, 10);
+    }
+
+    #[test]
+    fn test_init_config_requires_upgrade_authority() {
+        let mut svm = LiteSVM::new();
+        let upgrade_authority = Keypair::new();
+        let attacker = Keypair::new();
+        svm.airdrop(&attacker.pubkey(), 10 * LAMPORTS_PER_SOL)
+            .unwrap();
+        deploy_program(&mut svm, &upgrade_authority.pubkey());
+
+        //someone other than the upgrade authority can't claim the config
+        let init_config_ix = client::init_config_ix(&attacker.pubkey(), 0, &attacker.pubkey());
+        let message = Message::new(&[init_config_ix], Some(&attacker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&attacker], message, recent_blockhash);
+        assert_escrow_error(svm.send_transaction(tx), EscrowError::UnauthorizedAdmin);
+        assert!(svm.get_account(&client::pda::config()).is_none());
+    }
+
+    #[test]
+    fn test_make_while_paused() {
+        let (mut svm, maker) = setup();
+        let test_values = TestValues::new(&mut svm, &maker);
+
+        //pause the program
+        let update_config_ix =
+            client::update_config_ix(&maker.pubkey(), None, None, None, Some(true));
+        let message = Message::new(&[update_config_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        svm.send_transaction(tx).unwrap();
+
+        //make
+        let make_ix = client::make_ix(&test_values.offer, 10, 10, MakeArgs::default());
+
+        let message = Message::new(&[make_ix], Some(&maker.pubkey()));
+        svm.expire_blockhash();
+        let recent_blockhash = svm.latest_blockhash();
+
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        assert!(svm.send_transaction(tx).is_err());
+        msg!("Make transaction while paused failed");
+    }
+
+    #[test]
+    fn test_take_with_transfer_fee_mints() {
+        let (mut svm, maker) = setup();
+        let taker = Keypair::new();
+        let mint_authority = Keypair::new();
+        svm.airdrop(&taker.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+        svm.airdrop(&mint_authority.pubkey(), 10 * LAMPORTS_PER_SOL)
+            .unwrap();
+
+        //both legs charge a 1% transfer fee
+        let mint_a = create_transfer_fee_mint(&mut svm, &mint_authority, 100);
+        let mint_b = create_transfer_fee_mint(&mut svm, &mint_authority, 100);
+        create_token_2022_ata(&mut svm, &mint_authority, &maker.pubkey(), &mint_a, 10_000);
+        let taker_ata_b =
+            create_token_2022_ata(&mut svm, &mint_authority, &taker.pubkey(), &mint_b, 10_000);
+
+        let offer =
+            Offer::new(maker.pubkey(), 42, mint_a, mint_b).with_token_program(spl_token_2022::ID);
+        let escrow = offer.escrow();
+        let vault = offer.vault();
+        let config_account = svm.get_account(&client::pda::config()).unwrap();
+        let fee_recipient = client::decode_config(&config_account.data)
+            .unwrap()
+            .fee_recipient;
+
+        //make, asking for 1000 of mint b net of transfer fees
+        let make_ix = client::make_ix(
+            &offer,
+            1000,
+            1000,
+            MakeArgs {
+                receive_is_net: true,
+                ..Default::default()
+            },
+        );
+
+        let message = Message::new(&[make_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Make transaction successful: {:?}", tx_sig.signature);
+
+        //vault holds the deposit less the 1% mint a fee, and the escrow records it
+        assert_eq!(token_2022_balance(&svm, &vault), 990);
+        let escrow_account = svm.get_account(&escrow).unwrap();
+        let escrow_data = client::decode_escrow(&escrow_account.data).unwrap();
+        assert_eq!(escrow_data.deposited, 990);
+
+        //min_amount_a is checked against what the taker receives after the mint a fee,
+        //the 990 in the vault arrive as 980
+        let maker_ata_b = offer.ata(&maker.pubkey(), &mint_b);
+        let take_ix = client::take_ix(
+            &offer,
+            &taker.pubkey(),
+            &fee_recipient,
+            client::TakeArgs::new(1000, 990),
+            None,
+        );
+        let take_message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+
+        let tx = Transaction::new(&[&taker], take_message, recent_blockhash);
+        assert_escrow_error(svm.send_transaction(tx), EscrowError::SlippageExceeded);
 
         //take offer by the taker
-        let take_ix = Instruction {
-            program_id: PROGRAM_ID,
-            accounts: TakeAfterTime {
-                associated_token_program: spl_associated_token_account::ID,
-                escrow: test_values.escrow,
-                maker: maker.pubkey(),
-                maker_ata_b: test_values.maker_ata_b,
-                mint_a: test_values.mint_a,
-                mint_b: test_values.mint_b,
-                system_program: system_program::ID,
-                taker: test_values.taker.pubkey(),
-                taker_ata_a: test_values.taker_ata_a,
-                taker_ata_b: test_values.taker_ata_b,
-                token_program: TOKEN_PROGRAM_ID,
-                vault: test_values.vault,
-            }
-            .to_account_metas(None),
-            data: instruction::TakeAfterTime {}.data(),
-        };
-        let take_message = Message::new(&[take_ix], Some(&test_values.taker.pubkey()));
+        let take_ix = client::take_ix(
+            &offer,
+            &taker.pubkey(),
+            &fee_recipient,
+            client::TakeArgs::new(1000, 980),
+            None,
+        );
+        let take_message = Message::new(&[take_ix], Some(&taker.pubkey()));
         svm.expire_blockhash();
         let recent_blockhash = svm.latest_blockhash();
 
-        let tx = Transaction::new(&[&test_values.taker], take_message, recent_blockhash);
+        let tx = Transaction::new(&[&taker], take_message, recent_blockhash);
         let tx_sig = svm.send_transaction(tx).unwrap();
-        msg!(
-            "Take transaction successful after time threshold: {:?}",
-            tx_sig.signature
+        msg!("Take transaction successful: {:?}", tx_sig.signature);
+
+        //maker receives exactly the advertised amount, taker paid the gross
+        assert_eq!(token_2022_balance(&svm, &maker_ata_b), 1000);
+        assert_eq!(token_2022_balance(&svm, &taker_ata_b), 10_000 - 1011);
+        assert_eq!(
+            token_2022_balance(&svm, &offer.ata(&taker.pubkey(), &mint_a)),
+            980
         );
     }
 
     #[test]
-    fn refund_before_time() {
+    fn test_take_with_transfer_hook_mints() {
         let (mut svm, maker) = setup();
-        let test_values = TestValues::new(&mut svm, &maker);
-        let mut initial_time = svm.get_sysvar::<Clock>();
-        println!("initial time: {}", initial_time.unix_timestamp);
+        let taker = Keypair::new();
+        let mint_authority = Keypair::new();
+        svm.airdrop(&taker.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+        svm.airdrop(&mint_authority.pubkey(), 10 * LAMPORTS_PER_SOL)
+            .unwrap();
+        load_transfer_hook(&mut svm);
 
-        //make
-        let make_ix = Instruction {
-            program_id: PROGRAM_ID,
-            accounts: crate::accounts::Make {
-                maker: maker.pubkey(),
-                mint_a: test_values.mint_a,
-                mint_b: test_values.mint_b,
-                maker_ata_a: test_values.maker_ata_a,
-                escrow: test_values.escrow,
-                vault: test_values.vault,
-                associated_token_program: spl_associated_token_account::ID,
-                token_program: TOKEN_PROGRAM_ID,
-                system_program: system_program::ID,
-            }
-            .to_account_metas(None),
-            data: instruction::Make {
-                deposit: 10,
-                seed: test_values.escrow_seed,
-                receive: 10,
-            }
-            .data(),
-        };
+        //both legs run the whitelist hook on every transfer
+        let mint_a = create_transfer_hook_mint(&mut svm, &mint_authority);
+        let mint_b = create_transfer_hook_mint(&mut svm, &mint_authority);
+        create_token_2022_ata(&mut svm, &mint_authority, &maker.pubkey(), &mint_a, 10_000);
+        let taker_ata_b =
+            create_token_2022_ata(&mut svm, &mint_authority, &taker.pubkey(), &mint_b, 10_000);
+
+        let offer =
+            Offer::new(maker.pubkey(), 42, mint_a, mint_b).with_token_program(spl_token_2022::ID);
+        let escrow = offer.escrow();
+        let vault = offer.vault();
+        let config_account = svm.get_account(&client::pda::config()).unwrap();
+        let fee_recipient = client::decode_config(&config_account.data)
+            .unwrap()
+            .fee_recipient;
+
+        //every account tokens leave from has to be whitelisted, the vault is owned by the escrow
+        for owner in [maker.pubkey(), taker.pubkey(), escrow] {
+            whitelist(&mut svm, &mint_authority, &owner);
+        }
+
+        //make, mint a leaves the maker's account
+        let mut make_ix = client::make_ix(&offer, 1000, 1000, MakeArgs::default());
+        make_ix
+            .accounts
+            .extend(transfer_hook_accounts(&mint_a, &maker.pubkey()));
 
         let message = Message::new(&[make_ix], Some(&maker.pubkey()));
         let recent_blockhash = svm.latest_blockhash();
@@ -538,63 +877,1615 @@ mod tests {
         let tx = Transaction::new(&[&maker], message, recent_blockhash);
         let tx_sig = svm.send_transaction(tx).unwrap();
         msg!("Make transaction successful: {:?}", tx_sig.signature);
+        assert_eq!(token_2022_balance(&svm, &vault), 1000);
 
-        initial_time.unix_timestamp = initial_time.unix_timestamp.saturating_add(60 * 5 * 1000);
-        svm.set_sysvar::<Clock>(&initial_time);
-        initial_time = svm.get_sysvar::<Clock>();
-        println!("updated time: {}", initial_time.unix_timestamp);
+        //take, mint b leaves the taker's account and mint a the escrow's vault
+        let mut take_ix = client::take_ix(
+            &offer,
+            &taker.pubkey(),
+            &fee_recipient,
+            client::TakeArgs::new(1000, 1000),
+            None,
+        );
+        take_ix
+            .accounts
+            .extend(transfer_hook_accounts(&mint_b, &taker.pubkey()));
+        take_ix
+            .accounts
+            .extend(transfer_hook_accounts(&mint_a, &escrow));
 
-        //refund before permissible time
-        let refund_ix = Instruction {
-            program_id: PROGRAM_ID,
-            accounts: crate::accounts::RefundBeforeTime {
-                escrow: test_values.escrow,
-                maker: maker.pubkey(),
-                maker_ata_a: test_values.maker_ata_a,
-                mint_a: test_values.mint_a,
-                system_program: system_program::ID,
-                token_program: spl_token::ID,
-                vault: test_values.vault,
-            }
-            .to_account_metas(None),
-            data: instruction::RefundBeforeTime {}.data(),
-        };
-        let message = Message::new(&[refund_ix], Some(&maker.pubkey()));
-        svm.expire_blockhash();
+        let take_message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+
+        let tx = Transaction::new(&[&taker], take_message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Take transaction successful: {:?}", tx_sig.signature);
+
+        assert_eq!(
+            token_2022_balance(&svm, &offer.ata(&maker.pubkey(), &mint_b)),
+            1000
+        );
+        assert_eq!(token_2022_balance(&svm, &taker_ata_b), 10_000 - 1000);
+        assert_eq!(
+            token_2022_balance(&svm, &offer.ata(&taker.pubkey(), &mint_a)),
+            1000
+        );
+        assert!(svm.get_account(&escrow).is_none_or(|a| a.lamports == 0));
+    }
+
+    #[test]
+    fn test_take_sol_offer() {
+        let (mut svm, maker) = setup();
+        let taker = Keypair::new();
+        let mint_authority = Keypair::new();
+        svm.airdrop(&taker.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+        svm.airdrop(&mint_authority.pubkey(), 10 * LAMPORTS_PER_SOL)
+            .unwrap();
+
+        //maker offers native SOL, both legs live under Token-2022
+        let create_native_mint_ix = spl_token_2022::instruction::create_native_mint(
+            &spl_token_2022::ID,
+            &mint_authority.pubkey(),
+        )
+        .unwrap();
+        let message = Message::new(&[create_native_mint_ix], Some(&mint_authority.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&mint_authority], message, recent_blockhash);
+        svm.send_transaction(tx).unwrap();
+        let mint_a = spl_token_2022::native_mint::ID;
+        let mint_b = create_transfer_fee_mint(&mut svm, &mint_authority, 0);
+        let taker_ata_b =
+            create_token_2022_ata(&mut svm, &mint_authority, &taker.pubkey(), &mint_b, 10_000);
+
+        let offer =
+            Offer::new(maker.pubkey(), 7, mint_a, mint_b).with_token_program(spl_token_2022::ID);
+        let vault = offer.vault();
+        let taker_ata_a = offer.ata(&taker.pubkey(), &mint_a);
+        let config_account = svm.get_account(&client::pda::config()).unwrap();
+        let fee_recipient = client::decode_config(&config_account.data)
+            .unwrap()
+            .fee_recipient;
+
+        //make, wrapping 1 SOL from the maker's lamports
+        let make_ix = client::make_ix(&offer, LAMPORTS_PER_SOL, 1000, MakeArgs::default());
+
+        let message = Message::new(&[make_ix], Some(&maker.pubkey()));
         let recent_blockhash = svm.latest_blockhash();
+
         let tx = Transaction::new(&[&maker], message, recent_blockhash);
-        let tx_sig = svm.send_transaction(tx);
-        assert!(tx_sig.is_ok());
-        msg!(
-            "Refund successfull before permissible time with tx sign: {:?}",
-            tx_sig.unwrap().signature
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Make transaction successful: {:?}", tx_sig.signature);
+
+        assert_eq!(token_2022_balance(&svm, &vault), LAMPORTS_PER_SOL);
+
+        //take, the released SOL is unwrapped into the taker's wallet
+        let taker_lamports = svm.get_balance(&taker.pubkey()).unwrap();
+        let take_ix = client::take_ix(
+            &offer,
+            &taker.pubkey(),
+            &fee_recipient,
+            client::TakeArgs::new(1000, LAMPORTS_PER_SOL),
+            None,
         );
+        let take_message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
 
-        initial_time.unix_timestamp = initial_time.unix_timestamp.saturating_add(60 * 30 * 1000);
-        svm.set_sysvar::<Clock>(&initial_time);
-        println!("updated time: {}", svm.get_sysvar::<Clock>().unix_timestamp);
+        let tx = Transaction::new(&[&taker], take_message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Take transaction successful: {:?}", tx_sig.signature);
 
-        //refund after permissible time
-        let refund_ix = Instruction {
-            program_id: PROGRAM_ID,
-            accounts: crate::accounts::RefundBeforeTime {
-                escrow: test_values.escrow,
-                maker: maker.pubkey(),
-                maker_ata_a: test_values.maker_ata_a,
-                mint_a: test_values.mint_a,
-                system_program: system_program::ID,
-                token_program: spl_token::ID,
-                vault: test_values.vault,
-            }
-            .to_account_metas(None),
-            data: instruction::RefundBeforeTime {}.data(),
-        };
-        let message = Message::new(&[refund_ix], Some(&maker.pubkey()));
-        svm.expire_blockhash();
+        //taker only paid the fee and the rent of the maker's mint b token account
+        assert!(svm
+            .get_account(&taker_ata_a)
+            .is_none_or(|a| a.lamports == 0));
+        assert!(svm.get_balance(&taker.pubkey()).unwrap() > taker_lamports + LAMPORTS_PER_SOL / 2);
+        assert_eq!(token_2022_balance(&svm, &taker_ata_b), 10_000 - 1000);
+
+        //taker already holds wrapped SOL, it is not unwrapped by the next take
+        create_token_2022_ata(&mut svm, &taker, &taker.pubkey(), &mint_a, 0);
+        let wrap_ixs = [
+            solana_system_interface::instruction::transfer(
+                &taker.pubkey(),
+                &taker_ata_a,
+                LAMPORTS_PER_SOL,
+            ),
+            spl_token_2022::instruction::sync_native(&spl_token_2022::ID, &taker_ata_a).unwrap(),
+        ];
+        let message = Message::new(&wrap_ixs, Some(&taker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&taker], message, recent_blockhash);
+        svm.send_transaction(tx).unwrap();
+
+        let offer =
+            Offer::new(maker.pubkey(), 8, mint_a, mint_b).with_token_program(spl_token_2022::ID);
+        let make_ix = client::make_ix(&offer, LAMPORTS_PER_SOL, 1000, MakeArgs::default());
+        let message = Message::new(&[make_ix], Some(&maker.pubkey()));
         let recent_blockhash = svm.latest_blockhash();
         let tx = Transaction::new(&[&maker], message, recent_blockhash);
-        let tx_sig = svm.send_transaction(tx);
-        assert!(tx_sig.is_err());
-        msg!("Refund failed");
+        svm.send_transaction(tx).unwrap();
+
+        let take_ix = client::take_ix(
+            &offer,
+            &taker.pubkey(),
+            &fee_recipient,
+            client::TakeArgs::new(1000, LAMPORTS_PER_SOL),
+            None,
+        );
+        let take_message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&taker], take_message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Take transaction successful: {:?}", tx_sig.signature);
+
+        //the released SOL is added to the wrapped SOL the taker already held
+        assert_eq!(token_2022_balance(&svm, &taker_ata_a), 2 * LAMPORTS_PER_SOL);
+        assert_eq!(token_2022_balance(&svm, &taker_ata_b), 10_000 - 2000);
+    }
+
+    #[test]
+    fn test_take_paid_in_sol() {
+        let (mut svm, maker) = setup();
+        let taker = Keypair::new();
+        let mint_authority = Keypair::new();
+        svm.airdrop(&taker.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+        svm.airdrop(&mint_authority.pubkey(), 10 * LAMPORTS_PER_SOL)
+            .unwrap();
+
+        //maker asks for native SOL, both legs live under Token-2022
+        let create_native_mint_ix = spl_token_2022::instruction::create_native_mint(
+            &spl_token_2022::ID,
+            &mint_authority.pubkey(),
+        )
+        .unwrap();
+        let message = Message::new(&[create_native_mint_ix], Some(&mint_authority.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&mint_authority], message, recent_blockhash);
+        svm.send_transaction(tx).unwrap();
+        let mint_a = create_transfer_fee_mint(&mut svm, &mint_authority, 0);
+        let mint_b = spl_token_2022::native_mint::ID;
+        create_token_2022_ata(&mut svm, &mint_authority, &maker.pubkey(), &mint_a, 1000);
+
+        let offer =
+            Offer::new(maker.pubkey(), 8, mint_a, mint_b).with_token_program(spl_token_2022::ID);
+        let config_account = svm.get_account(&client::pda::config()).unwrap();
+        let fee_recipient = client::decode_config(&config_account.data)
+            .unwrap()
+            .fee_recipient;
+
+        //make, asking for 1 SOL
+        let make_ix = client::make_ix(&offer, 1000, LAMPORTS_PER_SOL, MakeArgs::default());
+
+        let message = Message::new(&[make_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Make transaction successful: {:?}", tx_sig.signature);
+
+        //take, the SOL goes from the taker's wallet straight to the maker's
+        let maker_lamports = svm.get_balance(&maker.pubkey()).unwrap();
+        let take_ix = client::take_ix(
+            &offer,
+            &taker.pubkey(),
+            &fee_recipient,
+            client::TakeArgs::new(LAMPORTS_PER_SOL, 1000),
+            None,
+        );
+        let take_message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+
+        let tx = Transaction::new(&[&taker], take_message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Take transaction successful: {:?}", tx_sig.signature);
+
+        assert!(svm.get_balance(&maker.pubkey()).unwrap() >= maker_lamports + LAMPORTS_PER_SOL);
+        assert_eq!(
+            token_2022_balance(&svm, &offer.ata(&taker.pubkey(), &mint_a)),
+            1000
+        );
+
+        //no wrapped SOL account was opened for the taker, the maker or the treasury
+        for owner in [taker.pubkey(), maker.pubkey(), fee_recipient] {
+            assert!(svm
+                .get_account(&offer.ata(&owner, &mint_b))
+                .is_none_or(|a| a.lamports == 0));
+        }
+    }
+
+    #[test]
+    fn test_amend() {
+        let (mut svm, maker) = setup();
+        let test_values = TestValues::new(&mut svm, &maker);
+        //make
+        let make_ix = client::make_ix(&test_values.offer, 10, 10, MakeArgs::default());
+
+        let message = Message::new(&[make_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Make transaction successful: {:?}", tx_sig.signature);
+
+        //amend: top up 5 and reprice to 20
+        let amend_ix = client::amend_ix(&test_values.offer, 5, 0, 20);
+        let message = Message::new(&[amend_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Amend transaction successful: {:?}", tx_sig.signature);
+
+        let vault_account = svm.get_account(&test_values.vault).unwrap();
+        let vault_data = spl_token::state::Account::unpack(&vault_account.data).unwrap();
+        assert_eq!(vault_data.amount, 15);
+
+        let escrow_account = svm.get_account(&test_values.escrow).unwrap();
+        let escrow_data = client::decode_escrow(&escrow_account.data).unwrap();
+        assert_eq!(escrow_data.receive, 20);
+        assert_eq!(escrow_data.deposited, 15);
+        assert_eq!(escrow_data.revision, 1);
+
+        //take against the stale revision fails
+        let take_ix = client::take_ix(
+            &test_values.offer,
+            &test_values.taker.pubkey(),
+            &test_values.fee_recipient,
+            client::TakeArgs {
+                expected_revision: Some(0),
+                expected_receive: 20,
+                min_amount_a: 15,
+            },
+            None,
+        );
+        let take_message = Message::new(&[take_ix], Some(&test_values.taker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&test_values.taker], take_message, recent_blockhash);
+        assert!(svm.send_transaction(tx).is_err());
+        msg!("Take against stale revision failed");
+
+        //take against the current revision
+        let take_ix = client::take_ix(
+            &test_values.offer,
+            &test_values.taker.pubkey(),
+            &test_values.fee_recipient,
+            client::TakeArgs {
+                expected_revision: Some(1),
+                expected_receive: 20,
+                min_amount_a: 15,
+            },
+            None,
+        );
+        let take_message = Message::new(&[take_ix], Some(&test_values.taker.pubkey()));
+        svm.expire_blockhash();
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&test_values.taker], take_message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Take transaction successful: {:?}", tx_sig.signature);
+
+        let maker_ata_b = svm.get_account(&test_values.maker_ata_b).unwrap();
+        let maker_ata_b_data = spl_token::state::Account::unpack(&maker_ata_b.data).unwrap();
+        assert_eq!(maker_ata_b_data.amount, 20);
+    }
+
+    #[test]
+    fn test_take_private_offer() {
+        let (mut svm, maker) = setup();
+        let test_values = TestValues::new(&mut svm, &maker);
+        let designated_taker = Keypair::new();
+        //make, reserved for the designated taker only
+        let make_ix = client::make_ix(
+            &test_values.offer,
+            10,
+            10,
+            MakeArgs {
+                allowed_taker: Some(designated_taker.pubkey()),
+                ..Default::default()
+            },
+        );
+
+        let message = Message::new(&[make_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Make transaction successful: {:?}", tx_sig.signature);
+
+        //take offer by someone other than the designated taker
+        let take_ix = client::take_ix(
+            &test_values.offer,
+            &test_values.taker.pubkey(),
+            &test_values.fee_recipient,
+            client::TakeArgs::new(10, 10),
+            None,
+        );
+        let take_message = Message::new(&[take_ix], Some(&test_values.taker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+
+        let tx = Transaction::new(&[&test_values.taker], take_message, recent_blockhash);
+        assert!(svm.send_transaction(tx).is_err());
+        msg!("Take by undesignated taker failed");
+
+        let escrow_account = svm.get_account(&test_values.escrow).unwrap();
+        let escrow_data = client::decode_escrow(&escrow_account.data).unwrap();
+        assert_eq!(escrow_data.allowed_taker, Some(designated_taker.pubkey()));
+    }
+
+    #[test]
+    fn test_refund() {
+        let (mut svm, maker) = setup();
+        let test_values = TestValues::new(&mut svm, &maker);
+        //make
+        let make_ix = client::make_ix(&test_values.offer, 10, 10, MakeArgs::default());
+
+        let message = Message::new(&[make_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Make transaction successful: {:?}", tx_sig.signature);
+
+        //refund
+        let refund_ix = client::refund_ix(&test_values.offer);
+        let message = Message::new(&[refund_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Refund successfull with tx sign: {:?}", tx_sig.signature);
+    }
+
+    #[test]
+    fn test_crank_refund() {
+        let (mut svm, maker) = setup();
+        let test_values = TestValues::new(&mut svm, &maker);
+        let mut initial_time = svm.get_sysvar::<Clock>();
+        let crank_tip = 1_000_000;
+        //make, expiring in 10 minutes with a tip for the cranker
+        let make_ix = client::make_ix(
+            &test_values.offer,
+            10,
+            10,
+            MakeArgs {
+                expires_at: initial_time.unix_timestamp + 60 * 10,
+                crank_tip,
+                ..Default::default()
+            },
+        );
+
+        let message = Message::new(&[make_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Make transaction successful: {:?}", tx_sig.signature);
+
+        let cranker = Keypair::new();
+        svm.airdrop(&cranker.pubkey(), LAMPORTS_PER_SOL).unwrap();
+        let crank_ix = client::crank_refund_ix(&test_values.offer, &cranker.pubkey());
+
+        //crank before expiry fails
+        let message = Message::new(std::slice::from_ref(&crank_ix), Some(&cranker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&cranker], message, recent_blockhash);
+        assert!(svm.send_transaction(tx).is_err());
+        msg!("Crank refund before expiry failed");
+
+        initial_time.unix_timestamp = initial_time.unix_timestamp.saturating_add(60 * 11);
+        svm.set_sysvar::<Clock>(&initial_time);
+
+        //anyone may crank once expired and is paid the tip
+        let cranker_lamports = svm.get_balance(&cranker.pubkey()).unwrap();
+        let message = Message::new(&[crank_ix], Some(&cranker.pubkey()));
+        svm.expire_blockhash();
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&cranker], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Crank refund successful: {:?}", tx_sig.signature);
+
+        let maker_ata_a = svm.get_account(&test_values.maker_ata_a).unwrap();
+        let maker_ata_a_data = spl_token::state::Account::unpack(&maker_ata_a.data).unwrap();
+        assert_eq!(maker_ata_a_data.amount, 100 * DECIMALS_PER_TOKEN);
+        assert!(svm
+            .get_account(&test_values.escrow)
+            .is_none_or(|a| a.lamports == 0));
+        assert!(svm.get_balance(&cranker.pubkey()).unwrap() > cranker_lamports);
+    }
+
+    #[test]
+    fn test_take_hashlocked_offer() {
+        let (mut svm, maker) = setup();
+        let test_values = TestValues::new(&mut svm, &maker);
+        let initial_time = svm.get_sysvar::<Clock>();
+        let preimage = b"cross-chain secret".to_vec();
+        let hashlock_args = MakeArgs {
+            expires_at: initial_time.unix_timestamp + 60 * 10,
+            hashlock: Some(hash(&preimage).to_bytes()),
+            allowed_taker: Some(test_values.taker.pubkey()),
+            ..Default::default()
+        };
+
+        //a hashlock without a named taker is rejected
+        let make_ix = client::make_ix(
+            &test_values.offer,
+            10,
+            10,
+            MakeArgs {
+                allowed_taker: None,
+                ..hashlock_args.clone()
+            },
+        );
+        let message = Message::new(&[make_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        assert_escrow_error(svm.send_transaction(tx), EscrowError::HashlockWithoutTaker);
+
+        //make, locked to the hash of the secret and the taker with a 10 minute timeout
+        let make_ix = client::make_ix(&test_values.offer, 10, 10, hashlock_args.clone());
+
+        let message = Message::new(&[make_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Make transaction successful: {:?}", tx_sig.signature);
+
+        //maker cannot refund before the timeout
+        let refund_ix = client::refund_ix(&test_values.offer);
+        let message = Message::new(&[refund_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        assert!(svm.send_transaction(tx).is_err());
+        msg!("Refund before the timeout failed");
+
+        //take with the wrong preimage fails
+        let take_ix = client::take_ix(
+            &test_values.offer,
+            &test_values.taker.pubkey(),
+            &test_values.fee_recipient,
+            client::TakeArgs::new(10, 10),
+            Some(b"wrong secret".to_vec()),
+        );
+        let take_message = Message::new(&[take_ix], Some(&test_values.taker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&test_values.taker], take_message, recent_blockhash);
+        assert!(svm.send_transaction(tx).is_err());
+        msg!("Take with the wrong preimage failed");
+
+        //anyone else watching for the preimage can't take with it
+        let front_runner = Keypair::new();
+        svm.airdrop(&front_runner.pubkey(), LAMPORTS_PER_SOL)
+            .unwrap();
+        let take_ix = client::take_ix(
+            &test_values.offer,
+            &front_runner.pubkey(),
+            &test_values.fee_recipient,
+            client::TakeArgs::new(10, 10),
+            Some(preimage.clone()),
+        );
+        let take_message = Message::new(&[take_ix], Some(&front_runner.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&front_runner], take_message, recent_blockhash);
+        assert_escrow_error(svm.send_transaction(tx), EscrowError::UnauthorizedTaker);
+        msg!("Take by another taker with the preimage failed");
+
+        //take revealing the preimage
+        let take_ix = client::take_ix(
+            &test_values.offer,
+            &test_values.taker.pubkey(),
+            &test_values.fee_recipient,
+            client::TakeArgs::new(10, 10),
+            Some(preimage.clone()),
+        );
+        let take_message = Message::new(&[take_ix], Some(&test_values.taker.pubkey()));
+        svm.expire_blockhash();
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&test_values.taker], take_message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Take transaction successful: {:?}", tx_sig.signature);
+
+        assert!(svm
+            .get_account(&test_values.escrow)
+            .is_none_or(|a| a.lamports == 0));
+
+        //a second hashlocked offer nobody takes before the timeout
+        let timed_out = Offer::new(
+            maker.pubkey(),
+            test_values.escrow_seed + 1,
+            test_values.mint_a,
+            test_values.mint_b,
+        );
+        let make_ix = client::make_ix(&timed_out, 10, 10, hashlock_args);
+        let message = Message::new(&[make_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        svm.send_transaction(tx).unwrap();
+
+        let mut clock = svm.get_sysvar::<Clock>();
+        clock.unix_timestamp = initial_time.unix_timestamp + 60 * 10;
+        svm.set_sysvar::<Clock>(&clock);
+
+        //after the timeout only the refund works, even with the preimage
+        let take_ix = client::take_ix(
+            &timed_out,
+            &test_values.taker.pubkey(),
+            &test_values.fee_recipient,
+            client::TakeArgs::new(10, 10),
+            Some(preimage),
+        );
+        let take_message = Message::new(&[take_ix], Some(&test_values.taker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&test_values.taker], take_message, recent_blockhash);
+        assert_escrow_error(svm.send_transaction(tx), EscrowError::OfferExpired);
+        msg!("Take after the timeout failed");
+
+        let refund_ix = client::refund_ix(&timed_out);
+        let message = Message::new(&[refund_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!(
+            "Refund after the timeout successful: {:?}",
+            tx_sig.signature
+        );
+
+        assert!(svm
+            .get_account(&timed_out.escrow())
+            .is_none_or(|a| a.lamports == 0));
+    }
+
+    #[test]
+    fn test_arbitrated_escrow() {
+        let (mut svm, maker) = setup();
+        let test_values = TestValues::new(&mut svm, &maker);
+        let arbiter = Keypair::new();
+        svm.airdrop(&arbiter.pubkey(), LAMPORTS_PER_SOL).unwrap();
+
+        //set a 1% protocol fee, maker is the config admin
+        let update_config_ix =
+            client::update_config_ix(&maker.pubkey(), None, Some(100), None, None);
+        let message = Message::new(&[update_config_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        svm.send_transaction(tx).unwrap();
+
+        //a net ask cannot be arbitrated
+        let make_ix = client::make_ix(
+            &test_values.offer,
+            10,
+            2000,
+            MakeArgs {
+                arbiter: Some(arbiter.pubkey()),
+                receive_is_net: true,
+                ..Default::default()
+            },
+        );
+        let message = Message::new(&[make_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        assert_escrow_error(svm.send_transaction(tx), EscrowError::InvalidArbiter);
+
+        //make, settled by the arbiter
+        let make_ix = client::make_ix(
+            &test_values.offer,
+            10,
+            2000,
+            MakeArgs {
+                arbiter: Some(arbiter.pubkey()),
+                ..Default::default()
+            },
+        );
+
+        let message = Message::new(&[make_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Make transaction successful: {:?}", tx_sig.signature);
+
+        //maker amends the ask before the taker's funding lands
+        let amend_ix = client::amend_ix(&test_values.offer, 0, 0, 4000);
+        let message = Message::new(&[amend_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        svm.send_transaction(tx).unwrap();
+
+        //funding against the old revision or the old ask fails
+        let fund_ix = client::fund_arbitrated_ix(
+            &test_values.offer,
+            &test_values.taker.pubkey(),
+            client::TakeArgs {
+                expected_revision: Some(0),
+                ..client::TakeArgs::new(4000, 10)
+            },
+        );
+        let message = Message::new(&[fund_ix], Some(&test_values.taker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&test_values.taker], message, recent_blockhash);
+        assert_escrow_error(svm.send_transaction(tx), EscrowError::RevisionMismatch);
+
+        let fund_ix = client::fund_arbitrated_ix(
+            &test_values.offer,
+            &test_values.taker.pubkey(),
+            client::TakeArgs::new(2000, 10),
+        );
+        let message = Message::new(&[fund_ix], Some(&test_values.taker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&test_values.taker], message, recent_blockhash);
+        assert_escrow_error(svm.send_transaction(tx), EscrowError::SlippageExceeded);
+        msg!("Funding against the amended ask failed");
+
+        //maker goes back to the original ask
+        let amend_ix = client::amend_ix(&test_values.offer, 0, 0, 2000);
+        let message = Message::new(&[amend_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        svm.send_transaction(tx).unwrap();
+
+        //taker funds its side into vault_b
+        let fund_ix = client::fund_arbitrated_ix(
+            &test_values.offer,
+            &test_values.taker.pubkey(),
+            client::TakeArgs::new(2000, 10),
+        );
+        let message = Message::new(&[fund_ix], Some(&test_values.taker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&test_values.taker], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Fund transaction successful: {:?}", tx_sig.signature);
+
+        //maker can no longer pull out on its own
+        let refund_ix = client::refund_ix(&test_values.offer);
+        let message = Message::new(&[refund_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        assert!(svm.send_transaction(tx).is_err());
+        msg!("Refund of a funded arbitrated escrow failed");
+
+        //taker disputes the deliverable
+        let dispute_ix = client::raise_dispute_ix(&test_values.offer, &test_values.taker.pubkey());
+        let message = Message::new(&[dispute_ix], Some(&test_values.taker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&test_values.taker], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Dispute transaction successful: {:?}", tx_sig.signature);
+
+        //a disputed escrow cannot be conceded by a party
+        let release_ix = client::release_ix(
+            &test_values.offer,
+            &maker.pubkey(),
+            &test_values.taker.pubkey(),
+            &test_values.fee_recipient,
+            10_000,
+        );
+        let message = Message::new(&[release_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        assert!(svm.send_transaction(tx).is_err());
+        msg!("Release by the maker of a disputed escrow failed");
+
+        //arbiter splits both vaults in half
+        let release_ix = client::release_ix(
+            &test_values.offer,
+            &arbiter.pubkey(),
+            &test_values.taker.pubkey(),
+            &test_values.fee_recipient,
+            5_000,
+        );
+        let message = Message::new(&[release_ix], Some(&arbiter.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&arbiter], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Release transaction successful: {:?}", tx_sig.signature);
+
+        let balance = |svm: &LiteSVM, ata: &Pubkey| {
+            let account = svm.get_account(ata).unwrap();
+            spl_token::state::Account::unpack(&account.data)
+                .unwrap()
+                .amount
+        };
+        assert_eq!(balance(&svm, &test_values.taker_ata_a), 5);
+        //the maker's half of mint b pays the protocol fee
+        assert_eq!(balance(&svm, &test_values.maker_ata_b), 990);
+        assert_eq!(balance(&svm, &test_values.treasury_ata_b), 10);
+        assert_eq!(
+            balance(&svm, &test_values.maker_ata_a),
+            100 * DECIMALS_PER_TOKEN - 5
+        );
+        assert_eq!(
+            balance(&svm, &test_values.taker_ata_b),
+            100 * DECIMALS_PER_TOKEN - 1000
+        );
+    }
+
+    #[test]
+    fn test_take_bundle() {
+        let (mut svm, maker) = setup();
+        let test_values = TestValues::new(&mut svm, &maker);
+        let mint_authority = Keypair::new();
+        svm.airdrop(&mint_authority.pubkey(), LAMPORTS_PER_SOL)
+            .unwrap();
+
+        //set a 1% protocol fee, maker is the config admin
+        let update_config_ix =
+            client::update_config_ix(&maker.pubkey(), None, Some(100), None, None);
+        let message = Message::new(&[update_config_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        svm.send_transaction(tx).unwrap();
+
+        //maker adds mint c to the offer and also asks for mint d
+        let mint_c = CreateMint::new(&mut svm, &mint_authority)
+            .decimals(TOKEN_DECIMALS)
+            .send()
+            .unwrap();
+        let mint_d = CreateMint::new(&mut svm, &mint_authority)
+            .decimals(TOKEN_DECIMALS)
+            .send()
+            .unwrap();
+        let maker_ata_c = CreateAssociatedTokenAccount::new(&mut svm, &maker, &mint_c)
+            .send()
+            .unwrap();
+        let taker_ata_d = CreateAssociatedTokenAccount::new(&mut svm, &test_values.taker, &mint_d)
+            .send()
+            .unwrap();
+        MintTo::new(&mut svm, &mint_authority, &mint_c, &maker_ata_c, 50)
+            .send()
+            .unwrap();
+        MintTo::new(&mut svm, &mint_authority, &mint_d, &taker_ata_d, 3000)
+            .send()
+            .unwrap();
+        let vault_c = associated_token::get_associated_token_address(&test_values.escrow, &mint_c);
+        let taker_ata_c =
+            associated_token::get_associated_token_address(&test_values.taker.pubkey(), &mint_c);
+        let maker_ata_d = associated_token::get_associated_token_address(&maker.pubkey(), &mint_d);
+        let treasury_ata_d =
+            associated_token::get_associated_token_address(&test_values.fee_recipient, &mint_d);
+
+        //make, bundle accounts are [mint, from, to] per leg
+        let mut make_ix = client::make_ix(
+            &test_values.offer,
+            10,
+            10,
+            MakeArgs {
+                bundle_a: vec![BundleLeg {
+                    mint: mint_c,
+                    amount: 50,
+                }],
+                bundle_b: vec![BundleLeg {
+                    mint: mint_d,
+                    amount: 3000,
+                }],
+                ..Default::default()
+            },
+        );
+        make_ix.accounts.extend([
+            AccountMeta::new_readonly(mint_c, false),
+            AccountMeta::new(maker_ata_c, false),
+            AccountMeta::new(vault_c, false),
+        ]);
+
+        let message = Message::new(&[make_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Make transaction successful: {:?}", tx_sig.signature);
+
+        //take, bundle_a legs first and then bundle_b legs with the fee recipient's account
+        let mut take_ix = client::take_ix(
+            &test_values.offer,
+            &test_values.taker.pubkey(),
+            &test_values.fee_recipient,
+            client::TakeArgs::new(10, 10),
+            None,
+        );
+        take_ix.accounts.extend([
+            AccountMeta::new_readonly(mint_c, false),
+            AccountMeta::new(vault_c, false),
+            AccountMeta::new(taker_ata_c, false),
+            AccountMeta::new_readonly(mint_d, false),
+            AccountMeta::new(taker_ata_d, false),
+            AccountMeta::new(maker_ata_d, false),
+            AccountMeta::new(treasury_ata_d, false),
+        ]);
+        let take_message = Message::new(&[take_ix], Some(&test_values.taker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+
+        let tx = Transaction::new(&[&test_values.taker], take_message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Take transaction successful: {:?}", tx_sig.signature);
+
+        let balance = |svm: &LiteSVM, ata: &Pubkey| {
+            let account = svm.get_account(ata).unwrap();
+            spl_token::state::Account::unpack(&account.data)
+                .unwrap()
+                .amount
+        };
+        assert_eq!(balance(&svm, &taker_ata_c), 50);
+        assert_eq!(balance(&svm, &maker_ata_d), 2970);
+        assert_eq!(balance(&svm, &treasury_ata_d), 30);
+        assert!(svm.get_account(&vault_c).is_none_or(|a| a.lamports == 0));
+    }
+
+    #[test]
+    fn take_within_time_window() {
+        let (mut svm, maker) = setup();
+        let test_values = TestValues::new(&mut svm, &maker);
+        let mut initial_time = svm.get_sysvar::<Clock>();
+        println!("initial time: {}", initial_time.unix_timestamp);
+        //make, takeable 10 minutes from now for 10 minutes
+        let make_ix = client::make_ix(
+            &test_values.offer,
+            10,
+            10,
+            MakeArgs {
+                take_not_before: initial_time.unix_timestamp + 60 * 10,
+                expires_at: initial_time.unix_timestamp + 60 * 20,
+                ..Default::default()
+            },
+        );
+
+        let message = Message::new(&[make_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Make transaction successful: {:?}", tx_sig.signature);
+
+        //take offer by the taker
+        let take_ix = client::take_ix(
+            &test_values.offer,
+            &test_values.taker.pubkey(),
+            &test_values.fee_recipient,
+            client::TakeArgs::new(10, 10),
+            None,
+        );
+        let take_message = Message::new(&[take_ix], Some(&test_values.taker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+
+        let tx = Transaction::new(&[&test_values.taker], take_message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx);
+        println!("tx_sig: {:?}", tx_sig);
+        assert!(tx_sig.is_err());
+        msg!("Take transaction before time failed");
+
+        initial_time.unix_timestamp = initial_time.unix_timestamp.saturating_add(60 * 15);
+        svm.set_sysvar::<Clock>(&initial_time);
+        println!("time set to {}", svm.get_sysvar::<Clock>().unix_timestamp);
+
+        //take offer by the taker
+        let take_ix = client::take_ix(
+            &test_values.offer,
+            &test_values.taker.pubkey(),
+            &test_values.fee_recipient,
+            client::TakeArgs::new(10, 10),
+            None,
+        );
+        let take_message = Message::new(&[take_ix], Some(&test_values.taker.pubkey()));
+        svm.expire_blockhash();
+        let recent_blockhash = svm.latest_blockhash();
+
+        let tx = Transaction::new(&[&test_values.taker], take_message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!(
+            "Take transaction successful after time threshold: {:?}",
+            tx_sig.signature
+        );
+    }
+
+    #[test]
+    fn take_dutch_auction() {
+        let (mut svm, maker) = setup();
+        let test_values = TestValues::new(&mut svm, &maker);
+        let mut initial_time = svm.get_sysvar::<Clock>();
+        //make, price decays from 100 to 20 over 100 seconds
+        let make_ix = client::make_ix(
+            &test_values.offer,
+            10,
+            100,
+            MakeArgs {
+                end_receive: 20,
+                auction_start: initial_time.unix_timestamp,
+                auction_end: initial_time.unix_timestamp + 100,
+                ..Default::default()
+            },
+        );
+
+        let message = Message::new(&[make_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Make transaction successful: {:?}", tx_sig.signature);
+
+        //halfway through the auction the price is 60
+        initial_time.unix_timestamp = initial_time.unix_timestamp.saturating_add(50);
+        svm.set_sysvar::<Clock>(&initial_time);
+
+        //a taker bidding below the current price fails
+        let take_ix = client::take_ix(
+            &test_values.offer,
+            &test_values.taker.pubkey(),
+            &test_values.fee_recipient,
+            client::TakeArgs::new(59, 10),
+            None,
+        );
+        let take_message = Message::new(&[take_ix], Some(&test_values.taker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&test_values.taker], take_message, recent_blockhash);
+        assert_escrow_error(svm.send_transaction(tx), EscrowError::SlippageExceeded);
+        msg!("Take below the auction price failed");
+
+        //take at the current price
+        let take_ix = client::take_ix(
+            &test_values.offer,
+            &test_values.taker.pubkey(),
+            &test_values.fee_recipient,
+            client::TakeArgs::new(60, 10),
+            None,
+        );
+        let take_message = Message::new(&[take_ix], Some(&test_values.taker.pubkey()));
+        svm.expire_blockhash();
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&test_values.taker], take_message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Take transaction successful: {:?}", tx_sig.signature);
+
+        let maker_ata_b = svm.get_account(&test_values.maker_ata_b).unwrap();
+        let maker_ata_b_data = spl_token::state::Account::unpack(&maker_ata_b.data).unwrap();
+        assert_eq!(maker_ata_b_data.amount, 60);
+    }
+
+    #[test]
+    fn take_auction_with_unbounded_start() {
+        let (mut svm, maker) = setup();
+        let test_values = TestValues::new(&mut svm, &maker);
+        let initial_time = svm.get_sysvar::<Clock>();
+        //make, an auction starting at the earliest representable time
+        let make_ix = client::make_ix(
+            &test_values.offer,
+            10,
+            100,
+            MakeArgs {
+                end_receive: 20,
+                auction_start: i64::MIN,
+                auction_end: initial_time.unix_timestamp + 100,
+                ..Default::default()
+            },
+        );
+
+        let message = Message::new(&[make_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Make transaction successful: {:?}", tx_sig.signature);
+
+        //the elapsed time overflows, take fails with an error instead of a panic
+        let take_ix = client::take_ix(
+            &test_values.offer,
+            &test_values.taker.pubkey(),
+            &test_values.fee_recipient,
+            client::TakeArgs::new(100, 10),
+            None,
+        );
+        let take_message = Message::new(&[take_ix], Some(&test_values.taker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&test_values.taker], take_message, recent_blockhash);
+        assert_escrow_error(svm.send_transaction(tx), EscrowError::MathOverflow);
+    }
+
+    #[test]
+    fn take_after_expiry() {
+        let (mut svm, maker) = setup();
+        let test_values = TestValues::new(&mut svm, &maker);
+        let mut initial_time = svm.get_sysvar::<Clock>();
+
+        //make, expiring in 10 minutes
+        let make_ix = client::make_ix(
+            &test_values.offer,
+            10,
+            10,
+            MakeArgs {
+                expires_at: initial_time.unix_timestamp + 60 * 10,
+                ..Default::default()
+            },
+        );
+
+        let message = Message::new(&[make_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Make transaction successful: {:?}", tx_sig.signature);
+
+        initial_time.unix_timestamp = initial_time.unix_timestamp.saturating_add(60 * 10);
+        svm.set_sysvar::<Clock>(&initial_time);
+
+        //take offer after expiry
+        let take_ix = client::take_ix(
+            &test_values.offer,
+            &test_values.taker.pubkey(),
+            &test_values.fee_recipient,
+            client::TakeArgs::new(10, 10),
+            None,
+        );
+        let take_message = Message::new(&[take_ix], Some(&test_values.taker.pubkey()));
+        svm.expire_blockhash();
+        let recent_blockhash = svm.latest_blockhash();
+
+        let tx = Transaction::new(&[&test_values.taker], take_message, recent_blockhash);
+        assert!(svm.send_transaction(tx).is_err());
+        msg!("Take transaction after expiry failed");
+    }
+
+    #[test]
+    fn refund_after_lock_window() {
+        let (mut svm, maker) = setup();
+        let test_values = TestValues::new(&mut svm, &maker);
+        let mut initial_time = svm.get_sysvar::<Clock>();
+        println!("initial time: {}", initial_time.unix_timestamp);
+
+        //make, locked for 10 minutes
+        let make_ix = client::make_ix(
+            &test_values.offer,
+            10,
+            10,
+            MakeArgs {
+                refund_not_before: initial_time.unix_timestamp + 60 * 10,
+                ..Default::default()
+            },
+        );
+
+        let message = Message::new(&[make_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Make transaction successful: {:?}", tx_sig.signature);
+
+        initial_time.unix_timestamp = initial_time.unix_timestamp.saturating_add(60 * 5);
+        svm.set_sysvar::<Clock>(&initial_time);
+        initial_time = svm.get_sysvar::<Clock>();
+        println!("updated time: {}", initial_time.unix_timestamp);
+
+        //refund inside the lock window
+        let refund_ix = client::refund_ix(&test_values.offer);
+        let message = Message::new(&[refund_ix], Some(&maker.pubkey()));
+        svm.expire_blockhash();
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx);
+        assert!(tx_sig.is_err());
+        msg!("Refund failed inside lock window");
+
+        initial_time.unix_timestamp = initial_time.unix_timestamp.saturating_add(60 * 10);
+        svm.set_sysvar::<Clock>(&initial_time);
+        println!("updated time: {}", svm.get_sysvar::<Clock>().unix_timestamp);
+
+        //refund after the lock window
+        let refund_ix = client::refund_ix(&test_values.offer);
+        let message = Message::new(&[refund_ix], Some(&maker.pubkey()));
+        svm.expire_blockhash();
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!(
+            "Refund successfull after lock window with tx sign: {:?}",
+            tx_sig.signature
+        );
+    }
+
+    #[test]
+    fn test_take_partial() {
+        let (mut svm, maker) = setup();
+        let test_values = TestValues::new(&mut svm, &maker);
+        //make
+        let make_ix = client::make_ix(&test_values.offer, 100, 10, MakeArgs::default());
+
+        let message = Message::new(&[make_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Make transaction successful: {:?}", tx_sig.signature);
+
+        //first fill: 4 of 10 mint b releases 40 of 100 mint a
+        let take_ix = client::take_partial_ix(
+            &test_values.offer,
+            &test_values.taker.pubkey(),
+            &test_values.fee_recipient,
+            4,
+            client::TakeArgs::new(10, 40),
+        );
+        let message = Message::new(&[take_ix], Some(&test_values.taker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&test_values.taker], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!(
+            "Partial take transaction successful: {:?}",
+            tx_sig.signature
+        );
+
+        let vault_account = svm.get_account(&test_values.vault).unwrap();
+        let vault_data = spl_token::state::Account::unpack(&vault_account.data).unwrap();
+        assert_eq!(vault_data.amount, 60);
+
+        let escrow_account = svm.get_account(&test_values.escrow).unwrap();
+        let escrow_data = client::decode_escrow(&escrow_account.data).unwrap();
+        assert_eq!(escrow_data.receive, 6);
+
+        //taking more than what is left fails
+        let take_ix = client::take_partial_ix(
+            &test_values.offer,
+            &test_values.taker.pubkey(),
+            &test_values.fee_recipient,
+            7,
+            client::TakeArgs::new(6, 0),
+        );
+        let message = Message::new(&[take_ix], Some(&test_values.taker.pubkey()));
+        svm.expire_blockhash();
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&test_values.taker], message, recent_blockhash);
+        assert!(svm.send_transaction(tx).is_err());
+
+        //final fill closes vault and escrow
+        let take_ix = client::take_partial_ix(
+            &test_values.offer,
+            &test_values.taker.pubkey(),
+            &test_values.fee_recipient,
+            6,
+            client::TakeArgs::new(6, 60),
+        );
+        let message = Message::new(&[take_ix], Some(&test_values.taker.pubkey()));
+        svm.expire_blockhash();
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&test_values.taker], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Final take transaction successful: {:?}", tx_sig.signature);
+
+        let taker_ata_a = svm.get_account(&test_values.taker_ata_a).unwrap();
+        let taker_ata_a_data = spl_token::state::Account::unpack(&taker_ata_a.data).unwrap();
+        assert_eq!(taker_ata_a_data.amount, 100);
+
+        assert!(svm
+            .get_account(&test_values.vault)
+            .is_none_or(|account| account.lamports == 0));
+        assert!(svm
+            .get_account(&test_values.escrow)
+            .is_none_or(|account| account.lamports == 0));
+    }
+
+    #[test]
+    fn test_crowd_escrow() {
+        let (mut svm, maker) = setup();
+        let test_values = TestValues::new(&mut svm, &maker);
+        let initial_time = svm.get_sysvar::<Clock>();
+        let vault_b = test_values.offer.vault_b();
+
+        //second taker, funded with mint b by the first
+        let taker_2 = Keypair::new();
+        svm.airdrop(&taker_2.pubkey(), 10 * LAMPORTS_PER_SOL)
+            .unwrap();
+        let taker_2_ata_a =
+            associated_token::get_associated_token_address(&taker_2.pubkey(), &test_values.mint_a);
+        let taker_2_ata_b =
+            CreateAssociatedTokenAccount::new(&mut svm, &taker_2, &test_values.mint_b)
+                .owner(&taker_2.pubkey())
+                .send()
+                .unwrap();
+        let transfer_ix = spl_token::instruction::transfer_checked(
+            &TOKEN_PROGRAM_ID,
+            &test_values.taker_ata_b,
+            &test_values.mint_b,
+            &taker_2_ata_b,
+            &test_values.taker.pubkey(),
+            &[],
+            6,
+            TOKEN_DECIMALS,
+        )
+        .unwrap();
+        let message = Message::new(&[transfer_ix], Some(&test_values.taker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&test_values.taker], message, recent_blockhash);
+        svm.send_transaction(tx).unwrap();
+
+        //make, filled by the crowd
+        let make_ix = client::make_ix(
+            &test_values.offer,
+            100,
+            10,
+            MakeArgs {
+                expires_at: initial_time.unix_timestamp + 60 * 10,
+                crowd: true,
+                ..Default::default()
+            },
+        );
+
+        let message = Message::new(&[make_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Make transaction successful: {:?}", tx_sig.signature);
+
+        //a crowd escrow cannot be taken in one go
+        let take_ix = client::take_ix(
+            &test_values.offer,
+            &test_values.taker.pubkey(),
+            &test_values.fee_recipient,
+            client::TakeArgs::new(10, 0),
+            None,
+        );
+        let message = Message::new(&[take_ix], Some(&test_values.taker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&test_values.taker], message, recent_blockhash);
+        assert!(svm.send_transaction(tx).is_err());
+        msg!("Take of a crowd escrow failed");
+
+        //maker raises the ask before the first contribution lands
+        let amend_ix = client::amend_ix(&test_values.offer, 0, 0, 20);
+        let message = Message::new(&[amend_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        svm.send_transaction(tx).unwrap();
+
+        let contribute_ix = client::contribute_ix(
+            &test_values.offer,
+            &test_values.taker.pubkey(),
+            &test_values.fee_recipient,
+            4,
+            Some(0),
+        );
+        let message = Message::new(&[contribute_ix], Some(&test_values.taker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&test_values.taker], message, recent_blockhash);
+        assert_escrow_error(svm.send_transaction(tx), EscrowError::RevisionMismatch);
+        msg!("Contribution against the old revision failed");
+
+        //maker goes back to the original ask
+        let amend_ix = client::amend_ix(&test_values.offer, 0, 0, 10);
+        let message = Message::new(&[amend_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        svm.send_transaction(tx).unwrap();
+
+        //4 and 6 of 10 mint b, the second contribution pays the maker
+        let receipt = |taker: &Pubkey| client::pda::receipt(&test_values.escrow, taker);
+        for (taker, amount) in [(&test_values.taker, 4), (&taker_2, 6)] {
+            let contribute_ix = client::contribute_ix(
+                &test_values.offer,
+                &taker.pubkey(),
+                &test_values.fee_recipient,
+                amount,
+                Some(2),
+            );
+            let message = Message::new(&[contribute_ix], Some(&taker.pubkey()));
+            let recent_blockhash = svm.latest_blockhash();
+            let tx = Transaction::new(&[taker], message, recent_blockhash);
+            let tx_sig = svm.send_transaction(tx).unwrap();
+            msg!("Contribute transaction successful: {:?}", tx_sig.signature);
+        }
+
+        let maker_ata_b = svm.get_account(&test_values.maker_ata_b).unwrap();
+        let maker_ata_b_data = spl_token::state::Account::unpack(&maker_ata_b.data).unwrap();
+        assert_eq!(maker_ata_b_data.amount, 10);
+        assert!(svm
+            .get_account(&vault_b)
+            .is_none_or(|account| account.lamports == 0));
+
+        //each taker claims its share of the vault, the last claim closes the escrow
+        for (taker, taker_ata_a, expected) in [
+            (&test_values.taker, test_values.taker_ata_a, 40),
+            (&taker_2, taker_2_ata_a, 60),
+        ] {
+            let claim_ix = client::claim_ix(&test_values.offer, &taker.pubkey());
+            let message = Message::new(&[claim_ix], Some(&taker.pubkey()));
+            let recent_blockhash = svm.latest_blockhash();
+            let tx = Transaction::new(&[taker], message, recent_blockhash);
+            let tx_sig = svm.send_transaction(tx).unwrap();
+            msg!("Claim transaction successful: {:?}", tx_sig.signature);
+
+            let taker_ata_a = svm.get_account(&taker_ata_a).unwrap();
+            let taker_ata_a_data = spl_token::state::Account::unpack(&taker_ata_a.data).unwrap();
+            assert_eq!(taker_ata_a_data.amount, expected);
+            assert!(svm
+                .get_account(&receipt(&taker.pubkey()))
+                .is_none_or(|account| account.lamports == 0));
+        }
+
+        assert!(svm
+            .get_account(&test_values.vault)
+            .is_none_or(|account| account.lamports == 0));
+        assert!(svm
+            .get_account(&test_values.escrow)
+            .is_none_or(|account| account.lamports == 0));
+    }
+
+    #[test]
+    fn test_refund_crowd_contribution() {
+        let (mut svm, maker) = setup();
+        let test_values = TestValues::new(&mut svm, &maker);
+        let mut initial_time = svm.get_sysvar::<Clock>();
+        let receipt = client::pda::receipt(&test_values.escrow, &test_values.taker.pubkey());
+
+        let make_ix = client::make_ix(
+            &test_values.offer,
+            100,
+            10,
+            MakeArgs {
+                expires_at: initial_time.unix_timestamp + 60 * 10,
+                crowd: true,
+                ..Default::default()
+            },
+        );
+
+        let message = Message::new(&[make_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Make transaction successful: {:?}", tx_sig.signature);
+
+        let taker_ata_b = svm.get_account(&test_values.taker_ata_b).unwrap();
+        let taker_balance_b = spl_token::state::Account::unpack(&taker_ata_b.data)
+            .unwrap()
+            .amount;
+
+        //4 of 10 mint b, the escrow stays unfilled
+        let contribute_ix = client::contribute_ix(
+            &test_values.offer,
+            &test_values.taker.pubkey(),
+            &test_values.fee_recipient,
+            4,
+            None,
+        );
+        let message = Message::new(&[contribute_ix], Some(&test_values.taker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&test_values.taker], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Contribute transaction successful: {:?}", tx_sig.signature);
+
+        let refund_contribution_ix =
+            || client::refund_contribution_ix(&test_values.offer, &test_values.taker.pubkey());
+
+        //the contribution is locked until the escrow expires
+        let message = Message::new(
+            &[refund_contribution_ix()],
+            Some(&test_values.taker.pubkey()),
+        );
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&test_values.taker], message, recent_blockhash);
+        assert_escrow_error(svm.send_transaction(tx), EscrowError::OfferNotExpired);
+        msg!("Refund contribution before expiry failed");
+
+        initial_time.unix_timestamp = initial_time.unix_timestamp.saturating_add(60 * 10);
+        svm.set_sysvar::<Clock>(&initial_time);
+
+        let message = Message::new(
+            &[refund_contribution_ix()],
+            Some(&test_values.taker.pubkey()),
+        );
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&test_values.taker], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!(
+            "Refund contribution transaction successful: {:?}",
+            tx_sig.signature
+        );
+
+        //the taker has all of its mint b back, the receipt and vault_b are closed
+        let taker_ata_b = svm.get_account(&test_values.taker_ata_b).unwrap();
+        let taker_ata_b_data = spl_token::state::Account::unpack(&taker_ata_b.data).unwrap();
+        assert_eq!(taker_ata_b_data.amount, taker_balance_b);
+        assert!(svm
+            .get_account(&receipt)
+            .is_none_or(|account| account.lamports == 0));
+        assert!(svm
+            .get_account(&test_values.offer.vault_b())
+            .is_none_or(|account| account.lamports == 0));
+
+        //nothing is left to claim
+        let claim_ix = client::claim_ix(&test_values.offer, &test_values.taker.pubkey());
+        let message = Message::new(&[claim_ix], Some(&test_values.taker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&test_values.taker], message, recent_blockhash);
+        assert!(svm.send_transaction(tx).is_err());
+        msg!("Claim after the refund failed");
+    }
+
+    #[test]
+    fn test_fill_signed_order() {
+        let (mut svm, maker) = setup();
+        let test_values = TestValues::new(&mut svm, &maker);
+        let delegate = client::pda::delegate();
+
+        //maker lets the program move up to 50 mint a, nothing is locked
+        let approve_ix = spl_token::instruction::approve(
+            &TOKEN_PROGRAM_ID,
+            &test_values.maker_ata_a,
+            &delegate,
+            &maker.pubkey(),
+            &[],
+            50,
+        )
+        .unwrap();
+        let message = Message::new(&[approve_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        svm.send_transaction(tx).unwrap();
+
+        let order = Order {
+            maker: maker.pubkey(),
+            mint_a: test_values.mint_a,
+            mint_b: test_values.mint_b,
+            amount_a: 50,
+            amount_b: 5,
+            nonce: 1,
+            expires_at: 0,
+            allowed_taker: None,
+        };
+        let signature = maker.sign_message(&order.message().unwrap());
+
+        let fill = |svm: &mut LiteSVM, order: &Order, signed: &Order| {
+            //the verify instruction covers `signed`, the fill submits `order`
+            let ixs = |order: &Order| {
+                client::fill_order_ixs(
+                    order,
+                    signature.as_ref().try_into().unwrap(),
+                    &test_values.taker.pubkey(),
+                    &test_values.fee_recipient,
+                    &TOKEN_PROGRAM_ID,
+                )
+            };
+            let [verify_ix, _] = ixs(signed);
+            let [_, fill_ix] = ixs(order);
+            let message = Message::new(&[verify_ix, fill_ix], Some(&test_values.taker.pubkey()));
+            svm.expire_blockhash();
+            let recent_blockhash = svm.latest_blockhash();
+            let tx = Transaction::new(&[&test_values.taker], message, recent_blockhash);
+            svm.send_transaction(tx).is_ok()
+        };
+
+        //terms that differ from what the maker signed are rejected
+        let tampered = Order {
+            amount_b: 1,
+            ..order.clone()
+        };
+        assert!(!fill(&mut svm, &tampered, &order));
+        assert!(!fill(&mut svm, &tampered, &tampered));
+
+        assert!(fill(&mut svm, &order, &order));
+        msg!("Fill order transaction successful");
+
+        let taker_ata_a = svm.get_account(&test_values.taker_ata_a).unwrap();
+        let taker_ata_a_data = spl_token::state::Account::unpack(&taker_ata_a.data).unwrap();
+        assert_eq!(taker_ata_a_data.amount, 50);
+        let maker_ata_b = svm.get_account(&test_values.maker_ata_b).unwrap();
+        let maker_ata_b_data = spl_token::state::Account::unpack(&maker_ata_b.data).unwrap();
+        assert_eq!(maker_ata_b_data.amount, 5);
+
+        //the nonce is spent, the same order can't fill twice
+        assert!(!fill(&mut svm, &order, &order));
+
+        //a cancelled nonce can't be filled either
+        let cancel_ix = client::cancel_order_ix(&maker.pubkey(), 2);
+        let message = Message::new(&[cancel_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!(
+            "Cancel order transaction successful: {:?}",
+            tx_sig.signature
+        );
+
+        let cancelled = Order {
+            nonce: 2,
+            ..order.clone()
+        };
+        assert!(!fill(&mut svm, &cancelled, &cancelled));
+    }
+
+    #[test]
+    fn test_migrate_legacy_escrow() {
+        let (mut svm, maker) = setup();
+        let test_values = TestValues::new(&mut svm, &maker);
+        let (_, bump) = client::pda::escrow(&maker.pubkey(), test_values.escrow_seed);
+
+        //an offer left by the first, unversioned release
+        let legacy = EscrowV0 {
+            seed: test_values.escrow_seed,
+            maker: maker.pubkey(),
+            mint_a: test_values.mint_a,
+            mint_b: test_values.mint_b,
+            receive: 10,
+            bump,
+            initialization_time: 0,
+        };
+        let mut data = Escrow::DISCRIMINATOR.to_vec();
+        legacy.serialize(&mut data).unwrap();
+        svm.set_account(
+            test_values.escrow,
+            Account {
+                lamports: svm.minimum_balance_for_rent_exemption(data.len()),
+                data,
+                owner: PROGRAM_ID,
+                executable: false,
+                rent_epoch: 0,
+            },
+        )
+        .unwrap();
+        CreateAssociatedTokenAccount::new(&mut svm, &maker, &test_values.mint_a)
+            .owner(&test_values.escrow)
+            .send()
+            .unwrap();
+        let deposit_ix = spl_token::instruction::transfer_checked(
+            &TOKEN_PROGRAM_ID,
+            &test_values.maker_ata_a,
+            &test_values.mint_a,
+            &test_values.vault,
+            &maker.pubkey(),
+            &[],
+            10,
+            TOKEN_DECIMALS,
+        )
+        .unwrap();
+        let message = Message::new(&[deposit_ix], Some(&maker.pubkey()));
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&maker], message, recent_blockhash);
+        svm.send_transaction(tx).unwrap();
+
+        let take_ix = client::take_ix(
+            &test_values.offer,
+            &test_values.taker.pubkey(),
+            &test_values.fee_recipient,
+            client::TakeArgs::new(10, 10),
+            None,
+        );
+
+        //the legacy layout can't be taken until it is migrated
+        let message = Message::new(
+            std::slice::from_ref(&take_ix),
+            Some(&test_values.taker.pubkey()),
+        );
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&test_values.taker], message, recent_blockhash);
+        assert_escrow_error(
+            svm.send_transaction(tx),
+            EscrowError::UnsupportedEscrowVersion,
+        );
+
+        let migrate_ix = client::migrate_escrow_ix(&test_values.offer, &test_values.taker.pubkey());
+        let message = Message::new(
+            std::slice::from_ref(&migrate_ix),
+            Some(&test_values.taker.pubkey()),
+        );
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&test_values.taker], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Migrate transaction successful: {:?}", tx_sig.signature);
+
+        let escrow_account = svm.get_account(&test_values.escrow).unwrap();
+        let escrow_data = client::decode_escrow(&escrow_account.data).unwrap();
+        assert_eq!(escrow_data.version, ESCROW_VERSION);
+        assert_eq!(escrow_data.seed, test_values.escrow_seed);
+        assert_eq!(escrow_data.receive, 10);
+        assert_eq!(escrow_data.deposited, 10);
+
+        //already current, a second migration is rejected
+        let message = Message::new(&[migrate_ix], Some(&test_values.taker.pubkey()));
+        svm.expire_blockhash();
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&test_values.taker], message, recent_blockhash);
+        assert!(svm.send_transaction(tx).is_err());
+
+        let message = Message::new(&[take_ix], Some(&test_values.taker.pubkey()));
+        svm.expire_blockhash();
+        let recent_blockhash = svm.latest_blockhash();
+        let tx = Transaction::new(&[&test_values.taker], message, recent_blockhash);
+        let tx_sig = svm.send_transaction(tx).unwrap();
+        msg!("Take transaction successful: {:?}", tx_sig.signature);
+
+        let taker_ata_a = svm.get_account(&test_values.taker_ata_a).unwrap();
+        let taker_ata_a_data = spl_token::state::Account::unpack(&taker_ata_a.data).unwrap();
+        assert_eq!(taker_ata_a_data.amount, 10);
     }
 }
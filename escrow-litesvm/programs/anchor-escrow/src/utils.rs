@@ -0,0 +1,248 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::{
+    associated_token::{create_idempotent, Create},
+    token::spl_token,
+    token_interface::{
+        close_account,
+        spl_token_2022::{
+            self,
+            extension::{
+                transfer_fee::{TransferFee, TransferFeeConfig},
+                BaseStateWithExtensions, StateWithExtensions,
+            },
+            onchain::{invoke_transfer_checked, invoke_transfer_checked_with_fee},
+        },
+        sync_native, transfer_checked, CloseAccount, SyncNative, TransferChecked,
+    },
+};
+
+use crate::EscrowError;
+
+// transfer fee of the current epoch, None for mints without a TransferFeeConfig extension
+fn current_transfer_fee(mint: &AccountInfo) -> Result<Option<TransferFee>> {
+    if mint.owner != &spl_token_2022::ID {
+        return Ok(None);
+    }
+
+    let data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    let Ok(fee_config) = mint_state.get_extension::<TransferFeeConfig>() else {
+        return Ok(None);
+    };
+
+    Ok(Some(*fee_config.get_epoch_fee(Clock::get()?.epoch)))
+}
+
+/// amount to send so that `net` lands in the destination after the mint's transfer fee
+pub fn gross_for_net(mint: &AccountInfo, net: u64) -> Result<u64> {
+    match current_transfer_fee(mint)? {
+        Some(fee) => Ok(fee
+            .calculate_pre_fee_amount(net)
+            .ok_or(EscrowError::MathOverflow)?),
+        None => Ok(net),
+    }
+}
+
+/// amount that lands in the destination when `gross` is sent, after the mint's transfer fee
+pub fn net_of_fee(mint: &AccountInfo, gross: u64) -> Result<u64> {
+    match current_transfer_fee(mint)? {
+        Some(fee) => Ok(gross
+            .checked_sub(fee.calculate_fee(gross).ok_or(EscrowError::MathOverflow)?)
+            .ok_or(EscrowError::MathOverflow)?),
+        None => Ok(gross),
+    }
+}
+
+/// drop-in for `transfer_checked` that is aware of Token-2022 mint extensions:
+/// - fee-bearing mints go through `transfer_checked_with_fee`, so the transfer fails instead of
+///   settling a different amount if the fee changes underneath
+/// - transfer-hook mints get their `ExtraAccountMetaList` resolved from the context's remaining
+///   accounts, which may hold the extra accounts of both escrow legs
+pub fn transfer_checked_with_extensions<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, TransferChecked<'info>>,
+    amount: u64,
+    decimals: u8,
+) -> Result<()> {
+    if ctx.accounts.mint.owner != &spl_token_2022::ID {
+        return transfer_checked(ctx, amount, decimals);
+    }
+
+    match current_transfer_fee(&ctx.accounts.mint)? {
+        Some(fee) => invoke_transfer_checked_with_fee(
+            ctx.program.key,
+            ctx.accounts.from,
+            ctx.accounts.mint,
+            ctx.accounts.to,
+            ctx.accounts.authority,
+            &ctx.remaining_accounts,
+            amount,
+            decimals,
+            fee.calculate_fee(amount).ok_or(EscrowError::MathOverflow)?,
+            ctx.signer_seeds,
+        )?,
+        None => invoke_transfer_checked(
+            ctx.program.key,
+            ctx.accounts.from,
+            ctx.accounts.mint,
+            ctx.accounts.to,
+            ctx.accounts.authority,
+            &ctx.remaining_accounts,
+            amount,
+            decimals,
+            ctx.signer_seeds,
+        )?,
+    }
+
+    Ok(())
+}
+
+/// native SOL legs are carried as wrapped SOL of either token program
+pub fn is_native_mint(mint: &Pubkey) -> bool {
+    mint == &spl_token::native_mint::ID || mint == &spl_token_2022::native_mint::ID
+}
+
+/// moves `amount` lamports from `owner` into its wrapped SOL account and syncs the token balance
+pub fn wrap_sol<'info>(
+    system_program: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    owner: AccountInfo<'info>,
+    account: AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    let cpi_accounts = Transfer {
+        from: owner,
+        to: account.clone(),
+    };
+
+    transfer(CpiContext::new(system_program, cpi_accounts), amount)?;
+
+    sync_native(CpiContext::new(token_program, SyncNative { account }))
+}
+
+/// closes a wrapped SOL account so its whole balance and rent return to `owner` as lamports,
+/// only for accounts opened by the same instruction so no wrapped SOL `owner` held is unwrapped
+pub fn unwrap_sol<'info>(
+    token_program: AccountInfo<'info>,
+    owner: AccountInfo<'info>,
+    account: AccountInfo<'info>,
+) -> Result<()> {
+    let cpi_accounts = CloseAccount {
+        account,
+        destination: owner.clone(),
+        authority: owner,
+    };
+
+    close_account(CpiContext::new(token_program, cpi_accounts))
+}
+
+/// creates `owner`'s associated token account for `mint` at `account` unless it already exists,
+/// the associated token program rejects any other address, returns whether it was created
+pub fn init_ata_if_needed<'info>(
+    account: AccountInfo<'info>,
+    owner: AccountInfo<'info>,
+    mint: AccountInfo<'info>,
+    payer: AccountInfo<'info>,
+    associated_token_program: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+) -> Result<bool> {
+    let created = account.data_is_empty();
+
+    let cpi_accounts = Create {
+        payer,
+        associated_token: account,
+        authority: owner,
+        mint,
+        system_program,
+        token_program,
+    };
+
+    create_idempotent(CpiContext::new(associated_token_program, cpi_accounts))?;
+
+    Ok(created)
+}
+
+/// accounts a taker pays mint b through, to the maker and the protocol treasury
+pub struct MintBPayment<'info> {
+    pub taker: AccountInfo<'info>,
+    pub taker_ata_b: AccountInfo<'info>,
+    pub maker: AccountInfo<'info>,
+    pub maker_ata_b: AccountInfo<'info>,
+    pub fee_recipient: AccountInfo<'info>,
+    pub treasury_ata_b: AccountInfo<'info>,
+    pub mint_b: AccountInfo<'info>,
+    pub decimals: u8,
+    pub associated_token_program: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+}
+
+impl<'info> MintBPayment<'info> {
+    /// pays `amount` of mint b, of which `fee` goes to the treasury and the rest to the maker
+    /// - net offers gross up the maker's leg so the taker pays the mint b transfer fee
+    /// - SOL is paid straight from the taker's lamports, so no wrapped SOL account is opened for
+    ///   the taker, the maker or the treasury
+    pub fn pay(
+        &self,
+        amount: u64,
+        fee: u64,
+        receive_is_net: bool,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let to_maker = amount.checked_sub(fee).ok_or(EscrowError::MathOverflow)?;
+        let to_maker = if receive_is_net {
+            gross_for_net(&self.mint_b, to_maker)?
+        } else {
+            to_maker
+        };
+
+        for (owner, account, amount) in [
+            (&self.maker, &self.maker_ata_b, to_maker),
+            (&self.fee_recipient, &self.treasury_ata_b, fee),
+        ] {
+            if amount == 0 {
+                continue;
+            }
+
+            if is_native_mint(self.mint_b.key) {
+                let cpi_accounts = Transfer {
+                    from: self.taker.clone(),
+                    to: owner.clone(),
+                };
+
+                transfer(
+                    CpiContext::new(self.system_program.clone(), cpi_accounts),
+                    amount,
+                )?;
+                continue;
+            }
+
+            init_ata_if_needed(
+                account.clone(),
+                owner.clone(),
+                self.mint_b.clone(),
+                self.taker.clone(),
+                self.associated_token_program.clone(),
+                self.token_program.clone(),
+                self.system_program.clone(),
+            )?;
+
+            let cpi_accounts = TransferChecked {
+                from: self.taker_ata_b.clone(),
+                to: account.clone(),
+                authority: self.taker.clone(),
+                mint: self.mint_b.clone(),
+            };
+
+            let cpi_ctx = CpiContext::new(self.token_program.clone(), cpi_accounts)
+                .with_remaining_accounts(remaining_accounts.to_vec());
+
+            transfer_checked_with_extensions(cpi_ctx, amount, self.decimals)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+use pinocchio::program_error::{ProgramError, ToStr};
+
+pub enum EscrowError {
+    InvalidInstructionLength,
+    InvalidEscrowAddress,
+    UnauthorizedMaker,
+    EscrowAlreadyExists,
+    InvalidAccountDiscriminator,
+    UnsupportedEscrowVersion,
+}
+
+impl From<EscrowError> for ProgramError {
+    fn from(value: EscrowError) -> Self {
+        Self::Custom(value as u32)
+    }
+}
+
+impl TryFrom<u32> for EscrowError {
+    type Error = ProgramError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        let err = match value {
+            0 => Self::InvalidInstructionLength,
+            1 => Self::InvalidEscrowAddress,
+            2 => Self::UnauthorizedMaker,
+            3 => Self::EscrowAlreadyExists,
+            4 => Self::InvalidAccountDiscriminator,
+            5 => Self::UnsupportedEscrowVersion,
+            _ => return Err(ProgramError::InvalidArgument),
+        };
+        Ok(err)
+    }
+}
+
+impl ToStr for EscrowError {
+    fn to_str<E>(&self) -> &'static str
+    where
+        E: 'static + ToStr + TryFrom<u32>,
+    {
+        match self {
+            EscrowError::InvalidInstructionLength => "Error: Wrong instruction data length.",
+            EscrowError::InvalidEscrowAddress => "Error: Escrow account is not the expected PDA.",
+            EscrowError::UnauthorizedMaker => "Error: Signer is not the escrow's maker.",
+            EscrowError::EscrowAlreadyExists => "Error: Escrow account is already initialized.",
+            EscrowError::InvalidAccountDiscriminator => "Error: Account is not an escrow.",
+            EscrowError::UnsupportedEscrowVersion => "Error: Unsupported escrow layout version.",
+        }
+    }
+}
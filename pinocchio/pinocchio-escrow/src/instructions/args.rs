@@ -0,0 +1,80 @@
+use pinocchio::program_error::ProgramError;
+
+use crate::error::EscrowError;
+
+/// Make and MakeV2 instruction data, integers little endian
+///
+/// Make: bump, amount_to_receive, amount_to_give
+/// MakeV2: bump, seed, amount_to_receive, amount_to_give
+pub struct MakeArgs {
+    pub bump: u8,
+    pub seed: Option<[u8; 8]>,
+    pub amount_to_receive: u64,
+    pub amount_to_give: u64,
+}
+
+impl MakeArgs {
+    pub const LEN: usize = 1 + 8 + 8;
+    pub const LEN_V2: usize = 1 + 8 + 8 + 8;
+
+    pub fn try_from_bytes(data: &[u8]) -> Result<Self, ProgramError> {
+        let data: &[u8; Self::LEN] = data
+            .try_into()
+            .map_err(|_| EscrowError::InvalidInstructionLength)?;
+
+        Ok(Self {
+            bump: data[0],
+            seed: None,
+            amount_to_receive: read_u64(data, 1),
+            amount_to_give: read_u64(data, 9),
+        })
+    }
+
+    pub fn try_from_bytes_v2(data: &[u8]) -> Result<Self, ProgramError> {
+        let data: &[u8; Self::LEN_V2] = data
+            .try_into()
+            .map_err(|_| EscrowError::InvalidInstructionLength)?;
+
+        Ok(Self {
+            bump: data[0],
+            seed: Some(le_bytes(data, 1)),
+            amount_to_receive: read_u64(data, 9),
+            amount_to_give: read_u64(data, 17),
+        })
+    }
+}
+
+/// Take and Cancel instruction data: the escrow bump, followed by the little endian seed for
+/// escrows opened with MakeV2
+pub struct TakeArgs {
+    pub bump: u8,
+    pub seed: Option<[u8; 8]>,
+}
+
+impl TakeArgs {
+    pub fn try_from_bytes(data: &[u8]) -> Result<Self, ProgramError> {
+        match data {
+            [bump] => Ok(Self {
+                bump: *bump,
+                seed: None,
+            }),
+            [bump, seed @ ..] => Ok(Self {
+                bump: *bump,
+                seed: Some(
+                    seed.try_into()
+                        .map_err(|_| EscrowError::InvalidInstructionLength)?,
+                ),
+            }),
+            [] => Err(EscrowError::InvalidInstructionLength.into()),
+        }
+    }
+}
+
+// callers check the data length before reading
+fn le_bytes(data: &[u8], offset: usize) -> [u8; 8] {
+    data[offset..offset + 8].try_into().unwrap()
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(le_bytes(data, offset))
+}
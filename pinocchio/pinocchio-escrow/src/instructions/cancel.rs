@@ -0,0 +1,114 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    msg,
+    program_error::ProgramError,
+    ProgramResult,
+};
+use pinocchio_pubkey::derive_address;
+use pinocchio_token::{
+    instructions::{CloseAccount, Transfer},
+    state::TokenAccount,
+};
+
+use crate::{error::EscrowError, instructions::TakeArgs, state::Escrow};
+
+pub fn process_cancel_instruction(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    msg!("Processing Cancel Instruction.");
+    let [
+        maker, //escrow account creator, gets the deposit and all rent back
+        mint_a,
+        escrow_account, //escrow pda account
+        maker_ata_a, //refund destination for token mint a
+        escrow_ata, //vault, stores token for mint_a from maker
+        _system_program,
+        _token_program,
+        _rest @ ..
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !maker.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if escrow_account.owner() != &crate::ID {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    //validate pda
+    //Cancel locates the escrow the same way Take does
+    let TakeArgs { bump, seed } = TakeArgs::try_from_bytes(data)?;
+    let escrow_seed = Escrow::seed_bytes(&seed);
+    let escrow_account_seed = [
+        b"escrow".as_ref(),
+        maker.key().as_slice(),
+        escrow_seed,
+        &[bump],
+    ];
+    let escrow_account_pda = derive_address(&escrow_account_seed, None, &crate::ID);
+    if *escrow_account.key() != escrow_account_pda {
+        return Err(EscrowError::InvalidEscrowAddress.into());
+    }
+
+    //only the maker who opened the escrow can cancel it
+    {
+        let escrow_account_state = Escrow::from_account_info(escrow_account)?;
+        if escrow_account_state.maker() != *maker.key() {
+            return Err(EscrowError::UnauthorizedMaker.into());
+        }
+        if escrow_account_state.seed() != seed {
+            return Err(EscrowError::InvalidEscrowAddress.into());
+        }
+        if escrow_account_state.mint_a() != *mint_a.key() {
+            return Err(ProgramError::InvalidAccountData);
+        }
+    }
+
+    msg!("escrow account validated!");
+
+    let amount = {
+        let escrow_ata_state = TokenAccount::from_account_info(escrow_ata)?;
+        let maker_ata_a_state = TokenAccount::from_account_info(maker_ata_a)?;
+        if escrow_ata_state.owner() != escrow_account.key()
+            || maker_ata_a_state.owner() != maker.key()
+        {
+            return Err(ProgramError::IllegalOwner);
+        }
+        if escrow_ata_state.mint() != mint_a.key() || maker_ata_a_state.mint() != mint_a.key() {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        escrow_ata_state.amount()
+    };
+
+    let bump = [bump.to_le()];
+    let seed = [
+        Seed::from(b"escrow"),
+        Seed::from(maker.key()),
+        Seed::from(escrow_seed),
+        Seed::from(&bump),
+    ];
+
+    //return the whole vault, it may hold more than amount_to_give if someone topped it up
+    Transfer {
+        amount,
+        authority: escrow_account,
+        from: escrow_ata,
+        to: maker_ata_a,
+    }
+    .invoke_signed(&[Signer::from(&seed)])?;
+
+    msg!("token a refunded to maker ata a");
+
+    CloseAccount {
+        account: escrow_ata,
+        destination: maker,
+        authority: escrow_account,
+    }
+    .invoke_signed(&[Signer::from(&seed)])?;
+
+    Escrow::close(escrow_account, maker)?;
+
+    msg!("vault and escrow account closed");
+
+    Ok(())
+}
@@ -9,11 +9,24 @@ use pinocchio::{
 use pinocchio_pubkey::derive_address;
 use pinocchio_system::instructions::CreateAccount;
 
-use crate::state::Escrow;
+use crate::{error::EscrowError, instructions::MakeArgs, state::Escrow};
 
 pub fn process_make_instruction(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
     msg!("Processing Make instruction");
 
+    open_escrow(accounts, MakeArgs::try_from_bytes(data)?)
+}
+
+/// opens the escrow at `[b"escrow", maker, seed, bump]` and moves `amount_to_give` of mint a
+/// into its vault, `seed` is only set for escrows opened with MakeV2
+pub(crate) fn open_escrow(accounts: &[AccountInfo], args: MakeArgs) -> ProgramResult {
+    let MakeArgs {
+        bump,
+        seed,
+        amount_to_receive,
+        amount_to_give,
+    } = args;
+
     let [maker, mint_a, mint_b, escrow_account, maker_ata, escrow_ata, system_program, token_program, _associated_token_program, _rent_sysvar @ ..] =
         accounts
     else {
@@ -29,22 +42,27 @@ pub fn process_make_instruction(accounts: &[AccountInfo], data: &[u8]) -> Progra
         }
     }
 
-    let bump = data[0];
-    let seed = [b"escrow".as_ref(), maker.key().as_slice(), &[bump]];
-    let seeds = &seed[..];
+    let stored_seed = seed;
+    let escrow_seed = Escrow::seed_bytes(&seed);
+    let seed = [
+        b"escrow".as_ref(),
+        maker.key().as_slice(),
+        escrow_seed,
+        &[bump],
+    ];
 
     let escrow_account_pda = derive_address(&seed, None, &crate::ID);
     log(&escrow_account_pda);
     log(&escrow_account.key());
-    assert_eq!(escrow_account_pda, *escrow_account.key());
-
-    let amount_to_receive = unsafe { *(data.as_ptr().add(1) as *const u64) };
-    let amount_to_give = unsafe { *(data.as_ptr().add(9) as *const u64) };
+    if escrow_account_pda != *escrow_account.key() {
+        return Err(EscrowError::InvalidEscrowAddress.into());
+    }
 
     let bump = [bump.to_le()];
     let seed = [
         Seed::from(b"escrow"),
         Seed::from(maker.key()),
+        Seed::from(escrow_seed),
         Seed::from(&bump),
     ];
     let seeds = Signer::from(&seed);
@@ -60,17 +78,18 @@ pub fn process_make_instruction(accounts: &[AccountInfo], data: &[u8]) -> Progra
         .invoke_signed(&[seeds.clone()])?;
 
         {
-            let escrow_state = Escrow::from_account_info(escrow_account)?;
+            let escrow_state = Escrow::init(escrow_account)?;
 
             escrow_state.set_maker(maker.key());
             escrow_state.set_mint_a(mint_a.key());
             escrow_state.set_mint_b(mint_b.key());
             escrow_state.set_amount_to_receive(amount_to_receive);
             escrow_state.set_amount_to_give(amount_to_give);
-            escrow_state.bump = data[0];
+            escrow_state.set_seed(stored_seed);
+            escrow_state.bump = bump[0];
         }
     } else {
-        return Err(pinocchio::program_error::ProgramError::IllegalOwner);
+        return Err(EscrowError::EscrowAlreadyExists.into());
     }
 
     pinocchio_associated_token_account::instructions::Create {
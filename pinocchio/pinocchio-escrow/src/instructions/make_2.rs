@@ -0,0 +1,10 @@
+use pinocchio::{account_info::AccountInfo, msg, ProgramResult};
+
+use crate::instructions::{open_escrow, MakeArgs};
+
+/// Make with a caller chosen seed, so one maker can have many escrows open at once
+pub fn process_make_instruction_v2(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    msg!("Processing MakeV2 instruction");
+
+    open_escrow(accounts, MakeArgs::try_from_bytes_v2(data)?)
+}
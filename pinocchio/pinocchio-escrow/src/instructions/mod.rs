@@ -1,9 +1,13 @@
+pub mod args;
+pub mod cancel;
 pub mod make;
-// pub mod make_2;
+pub mod make_2;
 pub mod take;
 
+pub use args::*;
+pub use cancel::*;
 pub use make::*;
-// pub use make_2::*;
+pub use make_2::*;
 pub use take::*;
 
 pub enum EscrowInstrctions {
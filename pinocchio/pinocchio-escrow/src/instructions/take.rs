@@ -6,14 +6,15 @@ use pinocchio::{
     ProgramResult,
 };
 use pinocchio_pubkey::derive_address;
+use pinocchio_token::state::TokenAccount;
 
-use crate::state::Escrow;
+use crate::{error::EscrowError, instructions::TakeArgs, state::Escrow};
 
 pub fn process_take_instruction(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
     msg!("Processing Take Instruction.");
     let [
         taker, //payer
-        maker,  //escrow account creator
+        maker,  //escrow account creator, receives the rent of the closed accounts
         _mint_a,
         _mint_b,
         escrow_account, //escrow pda account
@@ -49,19 +50,41 @@ pub fn process_take_instruction(accounts: &[AccountInfo], data: &[u8]) -> Progra
     msg!("validated account owners");
 
     //validate pda
-    let bump = data[0];
-    let escrow_account_seed = [b"escrow".as_ref(), maker.key().as_slice(), &[bump]]; 
+    let TakeArgs { bump, seed } = TakeArgs::try_from_bytes(data)?;
+    let escrow_seed = Escrow::seed_bytes(&seed);
+    let escrow_account_seed = [
+        b"escrow".as_ref(),
+        maker.key().as_slice(),
+        escrow_seed,
+        &[bump],
+    ];
     let escrow_account_pda = derive_address(&escrow_account_seed, None, &crate::ID);
-    assert_eq!(
-        *escrow_account.key(),
-        escrow_account_pda,
-        "Invalid PDA provided"
-    );
+    if *escrow_account.key() != escrow_account_pda {
+        return Err(EscrowError::InvalidEscrowAddress.into());
+    }
 
     msg!("escrow account validated!");
 
     //transfer the desired amount of
     let escrow_account_state = Escrow::from_account_info(escrow_account)?;
+    //the seed passed in has to be the one the escrow was opened with
+    if escrow_account_state.seed() != seed {
+        return Err(EscrowError::InvalidEscrowAddress.into());
+    }
+
+    //mint b has to be paid in the escrow's mint b, into a token account of the maker
+    {
+        let taker_ata_b_state = TokenAccount::from_account_info(taker_ata_b)?;
+        let maker_ata_b_state = TokenAccount::from_account_info(maker_ata_b)?;
+        if *maker_ata_b_state.owner() != escrow_account_state.maker() {
+            return Err(ProgramError::IllegalOwner);
+        }
+        if *taker_ata_b_state.mint() != escrow_account_state.mint_b()
+            || *maker_ata_b_state.mint() != escrow_account_state.mint_b()
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+    }
     pinocchio_token::instructions::Transfer {
         amount: escrow_account_state.amount_to_receive(),
         authority: taker,
@@ -76,14 +99,30 @@ pub fn process_take_instruction(accounts: &[AccountInfo], data: &[u8]) -> Progra
     let seed = [
         Seed::from(b"escrow"),
         Seed::from(maker.key()),
+        Seed::from(escrow_seed),
         Seed::from(&bump),
     ];
-    let signer_seeds = Signer::from(&seed);
+
+    //empty the whole vault so it can be closed, a top up by anyone else can't block the take
+    let vault_amount =
+        pinocchio_token::state::TokenAccount::from_account_info(escrow_ata)?.amount();
     pinocchio_token::instructions::Transfer {
-        amount: escrow_account_state.amount_to_give(),
+        amount: vault_amount,
         authority: escrow_account,
         from: escrow_ata,
         to: taker_ata_a,
     }
-    .invoke_signed(&[signer_seeds])
+    .invoke_signed(&[Signer::from(&seed)])?;
+
+    msg!("token a released to taker ata a");
+
+    //return the rent of the vault and the escrow to the maker, the zeroed escrow can't be taken again
+    pinocchio_token::instructions::CloseAccount {
+        account: escrow_ata,
+        destination: maker,
+        authority: escrow_account,
+    }
+    .invoke_signed(&[Signer::from(&seed)])?;
+
+    Escrow::close(escrow_account, maker)
 }
@@ -6,6 +6,7 @@ use crate::instructions::EscrowInstrctions;
 #[cfg(test)]
 mod tests;
 
+mod error;
 mod state;
 mod instructions;
 
@@ -19,16 +20,18 @@ pub fn process_instruction(
     instruction_data: &[u8],
 ) -> ProgramResult {
 
-    assert_eq!(program_id, &ID);
+    if program_id != &ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
 
     let (discriminator, data) = instruction_data.split_first()
         .ok_or(ProgramError::InvalidInstructionData)?;
 
     match EscrowInstrctions::try_from(discriminator)? {
         EscrowInstrctions::Make => instructions::process_make_instruction(accounts, data)?,
-        // EscrowInstrctions::MakeV2 => instructions::process_make_instruction_v2(accounts, data)?,
+        EscrowInstrctions::MakeV2 => instructions::process_make_instruction_v2(accounts, data)?,
         EscrowInstrctions::Take => instructions::process_take_instruction(accounts, data)?,
-        _ => return Err(pinocchio::program_error::ProgramError::InvalidInstructionData),
+        EscrowInstrctions::Cancel => instructions::process_cancel_instruction(accounts, data)?,
     }
     Ok(())
 }
\ No newline at end of file
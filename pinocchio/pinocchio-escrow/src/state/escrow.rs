@@ -1,37 +1,94 @@
-use pinocchio::{account_info::AccountInfo, program_error::ProgramError};
+use core::mem::{align_of, size_of};
 
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, ProgramResult};
+
+use crate::error::EscrowError;
+
+/// every field is a byte array, so the struct has no padding and can be read from account data
+/// at any address
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct Escrow {
+    discriminator: u8,
+    version: u8,
     maker: [u8; 32],
     mint_a: [u8; 32],
     mint_b: [u8; 32],
     amount_to_receive: [u8; 8],
     amount_to_give: [u8; 8],
+    seed: [u8; 8],
+    // 1 for escrows opened with MakeV2, 0 for Make, which derives the PDA without a seed
+    has_seed: u8,
     pub bump: u8,
 }
 
+const _: () = assert!(Escrow::LEN == 1 + 1 + 32 + 32 + 32 + 8 + 8 + 8 + 1 + 1);
+const _: () = assert!(align_of::<Escrow>() == 1);
+
 impl Escrow {
-    pub const LEN: usize = 32 + 32 + 32 + 8 + 8;
+    pub const LEN: usize = size_of::<Escrow>();
+    /// first byte of every escrow account, zeroed data (a closed escrow) never matches it
+    pub const DISCRIMINATOR: u8 = 1;
+    pub const VERSION: u8 = 1;
 
+    /// an initialized escrow owned by this program
     pub fn from_account_info(account_info: &AccountInfo) -> Result<&mut Self, ProgramError> {
+        let escrow = Self::from_account_info_unchecked(account_info)?;
+        if escrow.discriminator != Self::DISCRIMINATOR {
+            return Err(EscrowError::InvalidAccountDiscriminator.into());
+        }
+        if escrow.version != Self::VERSION {
+            return Err(EscrowError::UnsupportedEscrowVersion.into());
+        }
+
+        Ok(escrow)
+    }
+
+    /// stamps the discriminator and version on an escrow account the program just created
+    pub fn init(account_info: &AccountInfo) -> Result<&mut Self, ProgramError> {
+        let escrow = Self::from_account_info_unchecked(account_info)?;
+        if escrow.discriminator != 0 {
+            return Err(EscrowError::EscrowAlreadyExists.into());
+        }
+        escrow.discriminator = Self::DISCRIMINATOR;
+        escrow.version = Self::VERSION;
+
+        Ok(escrow)
+    }
+
+    fn from_account_info_unchecked(account_info: &AccountInfo) -> Result<&mut Self, ProgramError> {
+        if !account_info.is_owned_by(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
         let mut data = account_info.try_borrow_mut_data()?;
         if data.len() != Escrow::LEN {
             return Err(ProgramError::InvalidAccountData);
         }
 
-        if (data.as_ptr() as usize) % core::mem::align_of::<Self>() != 0 {
-            return Err(ProgramError::InvalidAccountData);
-        }
+        Ok(unsafe { &mut *(data.as_mut_ptr() as *mut Self) })
+    }
 
-        // let mut escrow = Escrow::default();
-        
-        // escrow.maker.copy_from_slice(&data[0..32]);
-        // escrow.mint_a.copy_from_slice(&data[32..64]);
-        // escrow.mint_b.copy_from_slice(&data[64..96]);
-        // escrow.amount_to_receive.copy_from_slice(&data[96..104]);
+    /// seed segment of the escrow PDA `[b"escrow", maker, seed, bump]`
+    ///
+    /// escrows opened with Make have no seed, an empty segment derives the same address as
+    /// leaving it out, so both kinds are derived and signed for the same way
+    pub fn seed_bytes(seed: &Option<[u8; 8]>) -> &[u8] {
+        seed.as_ref().map_or(&[][..], |seed| &seed[..])
+    }
 
-        Ok(unsafe { &mut *(data.as_mut_ptr() as *mut Self) })
+    /// moves the escrow's rent to `destination` and closes it, the zeroed data can't be read as
+    /// an escrow again within the same transaction
+    pub fn close(account_info: &AccountInfo, destination: &AccountInfo) -> ProgramResult {
+        {
+            let mut destination_lamports = destination.try_borrow_mut_lamports()?;
+            *destination_lamports = destination_lamports
+                .checked_add(account_info.lamports())
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+        }
+        account_info.try_borrow_mut_data()?.fill(0);
+        //zeroes the owner, lamports and data length
+        account_info.close()
     }
 
     pub fn maker(&self) -> pinocchio::pubkey::Pubkey {
@@ -73,4 +130,14 @@ impl Escrow {
     pub fn set_amount_to_give(&mut self, amount: u64) {
         self.amount_to_give = amount.to_le_bytes();
     }
+
+    /// little endian seed the escrow was opened with, None for escrows opened with Make
+    pub fn seed(&self) -> Option<[u8; 8]> {
+        (self.has_seed != 0).then_some(self.seed)
+    }
+
+    pub fn set_seed(&mut self, seed: Option<[u8; 8]>) {
+        self.seed = seed.unwrap_or_default();
+        self.has_seed = seed.is_some() as u8;
+    }
 }
\ No newline at end of file
@@ -0,0 +1,69 @@
+use litesvm::{types::TransactionResult, LiteSVM};
+use litesvm_token::spl_token::{self, solana_program::program_pack::Pack};
+use solana_instruction::{AccountMeta, Instruction};
+use solana_keypair::Keypair;
+use solana_pubkey::Pubkey;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+
+use crate::tests::make::{make_insn, program_id, setup, MakerAssociatedValues};
+
+#[test]
+pub fn test_cancel_instruction() {
+    let program_id = program_id();
+    let (mut svm, payer) = setup();
+    let maker_data = MakerAssociatedValues::generate_associated_values(&mut svm, &payer);
+
+    make_insn(&mut svm, &program_id, &payer, &maker_data).unwrap();
+
+    //only the maker can cancel
+    let stranger = Keypair::new();
+    svm.airdrop(&stranger.pubkey(), 1_000_000_000).unwrap();
+    assert!(cancel_insn(&mut svm, &program_id, &stranger, &maker_data).is_err());
+
+    let tx = cancel_insn(&mut svm, &program_id, &payer, &maker_data).unwrap();
+    println!("Cancel insn successful with tx: {}", tx.signature);
+
+    //the whole deposit is back with the maker, vault and escrow are closed
+    let maker_ata_a = svm.get_account(&maker_data.maker_ata_a).unwrap();
+    let maker_ata_a_state = spl_token::state::Account::unpack(&maker_ata_a.data).unwrap();
+    assert_eq!(maker_ata_a_state.amount, 1000000000);
+    assert!(svm
+        .get_account(&maker_data.vault)
+        .is_none_or(|account| account.lamports == 0));
+    assert!(svm
+        .get_account(&maker_data.escrow.0)
+        .is_none_or(|account| account.lamports == 0));
+}
+
+fn cancel_insn(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    maker: &Keypair,
+    maker_data: &MakerAssociatedValues,
+) -> TransactionResult {
+    let cancel_data = [vec![2u8], maker_data.escrow_address_data()].concat();
+    let cancel_accounts = [
+        AccountMeta::new(maker.pubkey(), true), //escrow account creator, receives the refund and rent
+        AccountMeta::new_readonly(maker_data.mint_a, false),
+        AccountMeta::new(maker_data.escrow.0, false), //escrow pda account
+        AccountMeta::new(maker_data.maker_ata_a, false), //refund destination for token mint a
+        AccountMeta::new(maker_data.vault, false),    //vault, stores token for mint_a from maker
+        AccountMeta::new_readonly(maker_data.system_program, false),
+        AccountMeta::new_readonly(maker_data.token_program, false),
+    ];
+    let cancel_ix = Instruction {
+        program_id: *program_id,
+        accounts: cancel_accounts.to_vec(),
+        data: cancel_data,
+    };
+
+    let tx = Transaction::new_signed_with_payer(
+        &[cancel_ix],
+        Some(&maker.pubkey()),
+        &[maker],
+        svm.latest_blockhash(),
+    );
+
+    svm.send_transaction(tx)
+}
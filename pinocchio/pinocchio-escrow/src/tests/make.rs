@@ -53,6 +53,7 @@ pub(super) struct MakerAssociatedValues {
     pub maker_ata_a: Pubkey,
     pub escrow: (Pubkey, u8),
     pub vault: Pubkey,
+    pub seed: Option<u64>,
     pub amount_to_give: u64,
     pub amount_to_receive: u64,
     pub associated_token_program: Pubkey,
@@ -116,6 +117,7 @@ impl MakerAssociatedValues {
             maker_ata_a,
             escrow,
             vault,
+            seed: None,
             amount_to_give,
             amount_to_receive,
             associated_token_program,
@@ -123,6 +125,32 @@ impl MakerAssociatedValues {
             token_program,
         }
     }
+
+    /// the same offer opened with MakeV2 under `seed`, escrow and vault move to the seeded PDA
+    pub(super) fn with_seed(self, maker: &Pubkey, seed: u64) -> Self {
+        let escrow = Pubkey::find_program_address(
+            &[b"escrow".as_ref(), maker.as_ref(), &seed.to_le_bytes()],
+            &PROGRAM_ID.parse().unwrap(),
+        );
+        let vault =
+            spl_associated_token_account::get_associated_token_address(&escrow.0, &self.mint_a);
+
+        Self {
+            escrow,
+            vault,
+            seed: Some(seed),
+            ..self
+        }
+    }
+
+    /// Take and Cancel data locating the escrow: its bump, then the seed if it has one
+    pub(super) fn escrow_address_data(&self) -> Vec<u8> {
+        let mut data = vec![self.escrow.1];
+        if let Some(seed) = self.seed {
+            data.extend_from_slice(&seed.to_le_bytes());
+        }
+        data
+    }
 }
 
 #[test]
@@ -141,6 +169,25 @@ pub fn test_make_instruction() {
     msg!("CUs Consumed: {}", tx.compute_units_consumed);
 }
 
+#[test]
+pub fn test_make_v2_instruction() {
+    let (mut svm, payer) = setup();
+
+    let program_id = program_id();
+
+    //two offers from the same maker live side by side under different seeds
+    for seed in [1u64, 2] {
+        let values = MakerAssociatedValues::generate_associated_values(&mut svm, &payer)
+            .with_seed(&payer.pubkey(), seed);
+
+        make_insn(&mut svm, &program_id, &payer, &values).unwrap();
+
+        let escrow_account = svm.get_account(&values.escrow.0).unwrap();
+        assert_eq!(escrow_account.owner, program_id);
+    }
+    msg!("\n\nMakeV2 transactions sucessfull");
+}
+
 pub(super) fn make_insn(
     svm: &mut LiteSVM,
     program_id: &Pubkey,
@@ -150,14 +197,24 @@ pub(super) fn make_insn(
     let bump: u8 = values.escrow.1;
     msg!("Bump: {}", bump);
 
-    // Create the "Make" instruction to deposit tokens into the escrow
-    let make_data = [
-        vec![0u8], // Discriminator for "Make" instruction
-        bump.to_le_bytes().to_vec(),
-        values.amount_to_receive.to_le_bytes().to_vec(),
-        values.amount_to_give.to_le_bytes().to_vec(),
-    ]
-    .concat();
+    // Create the "Make" instruction to deposit tokens into the escrow, "MakeV2" for seeded escrows
+    let make_data = match values.seed {
+        None => [
+            vec![0u8], // Discriminator for "Make" instruction
+            bump.to_le_bytes().to_vec(),
+            values.amount_to_receive.to_le_bytes().to_vec(),
+            values.amount_to_give.to_le_bytes().to_vec(),
+        ]
+        .concat(),
+        Some(seed) => [
+            vec![3u8], // Discriminator for "MakeV2" instruction
+            bump.to_le_bytes().to_vec(),
+            seed.to_le_bytes().to_vec(),
+            values.amount_to_receive.to_le_bytes().to_vec(),
+            values.amount_to_give.to_le_bytes().to_vec(),
+        ]
+        .concat(),
+    };
     let make_ix = Instruction {
         program_id: *program_id,
         accounts: vec![
@@ -1,2 +1,3 @@
+mod cancel;
 mod make;
 mod take;
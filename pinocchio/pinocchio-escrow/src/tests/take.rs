@@ -1,5 +1,5 @@
 use litesvm::{types::TransactionResult, LiteSVM};
-use litesvm_token::{CreateAssociatedTokenAccount, MintTo};
+use litesvm_token::{CreateAssociatedTokenAccount, CreateMint, MintTo};
 use solana_instruction::{AccountMeta, Instruction};
 use solana_keypair::Keypair;
 use solana_native_token::LAMPORTS_PER_SOL;
@@ -33,6 +33,188 @@ pub fn test_take_instruction() {
     )
     .unwrap();
     println!("Take insn successful with tx: {}", tx.signature);
+
+    //vault and escrow are closed to the maker, the offer can't be taken twice
+    assert!(svm
+        .get_account(&maker_data.vault)
+        .is_none_or(|account| account.lamports == 0));
+    assert!(svm
+        .get_account(&maker_data.escrow.0)
+        .is_none_or(|account| account.lamports == 0));
+
+    svm.expire_blockhash();
+    assert!(take_insn(
+        &mut svm,
+        &program_id,
+        &payer.pubkey(),
+        &maker_data,
+        &taker_data,
+    )
+    .is_err());
+}
+
+#[test]
+pub fn test_take_seeded_escrow() {
+    let program_id = program_id();
+    let (mut svm, payer) = setup();
+    let maker_data = MakerAssociatedValues::generate_associated_values(&mut svm, &payer)
+        .with_seed(&payer.pubkey(), 7);
+
+    make_insn(&mut svm, &program_id, &payer, &maker_data).unwrap();
+
+    let taker_data = TakerAssociatedValues::generate_associated_values(
+        &mut svm,
+        &payer,
+        &payer.pubkey(),
+        &maker_data,
+    );
+
+    let tx = take_insn(
+        &mut svm,
+        &program_id,
+        &payer.pubkey(),
+        &maker_data,
+        &taker_data,
+    )
+    .unwrap();
+    println!(
+        "Take of a seeded escrow successful with tx: {}",
+        tx.signature
+    );
+}
+
+#[test]
+pub fn test_take_rejects_malformed_data() {
+    let program_id = program_id();
+    let (mut svm, payer) = setup();
+    let maker_data = MakerAssociatedValues::generate_associated_values(&mut svm, &payer);
+
+    make_insn(&mut svm, &program_id, &payer, &maker_data).unwrap();
+
+    let taker_data = TakerAssociatedValues::generate_associated_values(
+        &mut svm,
+        &payer,
+        &payer.pubkey(),
+        &maker_data,
+    );
+
+    //no bump, and a seed cut short, fail with an error instead of reading past the data
+    for take_data in [vec![1u8], vec![1u8, maker_data.escrow.1, 7, 0, 0]] {
+        let result = take_insn_with_data(
+            &mut svm,
+            &program_id,
+            &payer.pubkey(),
+            &maker_data,
+            &taker_data,
+            take_data,
+        );
+        assert!(result.is_err());
+    }
+
+    take_insn(
+        &mut svm,
+        &program_id,
+        &payer.pubkey(),
+        &maker_data,
+        &taker_data,
+    )
+    .unwrap();
+}
+
+#[test]
+pub fn test_take_rejects_non_escrow_account() {
+    let program_id = program_id();
+    let (mut svm, payer) = setup();
+    let maker_data = MakerAssociatedValues::generate_associated_values(&mut svm, &payer);
+
+    make_insn(&mut svm, &program_id, &payer, &maker_data).unwrap();
+
+    let taker_data = TakerAssociatedValues::generate_associated_values(
+        &mut svm,
+        &payer,
+        &payer.pubkey(),
+        &maker_data,
+    );
+
+    //same size and owner as an escrow, but another account type
+    let mut escrow_account = svm.get_account(&maker_data.escrow.0).unwrap();
+    escrow_account.data[0] = 2;
+    svm.set_account(maker_data.escrow.0, escrow_account)
+        .unwrap();
+
+    assert!(take_insn(
+        &mut svm,
+        &program_id,
+        &payer.pubkey(),
+        &maker_data,
+        &taker_data,
+    )
+    .is_err());
+}
+
+#[test]
+pub fn test_take_rejects_foreign_mint_b_accounts() {
+    let program_id = program_id();
+    let (mut svm, payer) = setup();
+    let maker_data = MakerAssociatedValues::generate_associated_values(&mut svm, &payer);
+
+    make_insn(&mut svm, &program_id, &payer, &maker_data).unwrap();
+
+    let taker_data = TakerAssociatedValues::generate_associated_values(
+        &mut svm,
+        &payer,
+        &payer.pubkey(),
+        &maker_data,
+    );
+
+    //a worthless mint c, held by both the taker and the maker
+    let mint_c = CreateMint::new(&mut svm, &payer)
+        .decimals(6)
+        .authority(&payer.pubkey())
+        .send()
+        .unwrap();
+    let taker_ata_c = CreateAssociatedTokenAccount::new(&mut svm, &taker_data.taker, &mint_c)
+        .token_program_id(&maker_data.token_program)
+        .send()
+        .unwrap();
+    let maker_ata_c = CreateAssociatedTokenAccount::new(&mut svm, &taker_data.taker, &mint_c)
+        .token_program_id(&maker_data.token_program)
+        .owner(&payer.pubkey())
+        .send()
+        .unwrap();
+    MintTo::new(&mut svm, &payer, &mint_c, &taker_ata_c, 1000000000)
+        .send()
+        .unwrap();
+
+    //mint b paid back to the taker, and mint c paid instead of mint b
+    for (taker_ata_b, maker_ata_b) in [
+        (taker_data.taker_ata_b, taker_data.taker_ata_b),
+        (taker_ata_c, maker_ata_c),
+    ] {
+        let redirected = TakerAssociatedValues {
+            taker: taker_data.taker.insecure_clone(),
+            taker_ata_b,
+            maker_ata_b,
+            ..taker_data
+        };
+        assert!(take_insn(
+            &mut svm,
+            &program_id,
+            &payer.pubkey(),
+            &maker_data,
+            &redirected,
+        )
+        .is_err());
+    }
+
+    take_insn(
+        &mut svm,
+        &program_id,
+        &payer.pubkey(),
+        &maker_data,
+        &taker_data,
+    )
+    .unwrap();
 }
 
 pub(super) struct TakerAssociatedValues {
@@ -92,14 +274,24 @@ fn take_insn(
     maker_data: &MakerAssociatedValues,
     taker_data: &TakerAssociatedValues,
 ) -> TransactionResult {
-    let bump = maker_data.escrow.1;
-    let take_data = [vec![1u8], bump.to_le_bytes().to_vec()].concat();
+    let take_data = [vec![1u8], maker_data.escrow_address_data()].concat();
+    take_insn_with_data(svm, program_id, maker, maker_data, taker_data, take_data)
+}
+
+fn take_insn_with_data(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    maker: &Pubkey,
+    maker_data: &MakerAssociatedValues,
+    taker_data: &TakerAssociatedValues,
+    take_data: Vec<u8>,
+) -> TransactionResult {
     let take_accounts = [
         AccountMeta::new(taker_data.taker.pubkey(), true),
-        AccountMeta::new_readonly(*maker, false), //escrow account creator
+        AccountMeta::new(*maker, false), //escrow account creator, receives the closed accounts' rent
         AccountMeta::new_readonly(maker_data.mint_a, false), //receiving from escrow, had been deposited by maker
         AccountMeta::new_readonly(maker_data.mint_b, false), //sending to maker
-        AccountMeta::new_readonly(maker_data.escrow.0, false), //escrow pda account
+        AccountMeta::new(maker_data.escrow.0, false),        //escrow pda account
         AccountMeta::new(taker_data.taker_ata_a, false), //receiving token account of taker for mint a
         AccountMeta::new(taker_data.taker_ata_b, false), //sending token account of taker for mint b
         AccountMeta::new(taker_data.maker_ata_b, false), //transfer destination for token mint b